@@ -12,47 +12,92 @@ mod factory {
         },
         ToAccountId,
     };
-		
-		#[ink(event)]
-		pub struct OwnerChanged {
-			pub old_owner: AccountId,
-			pub new_owner: AccountId,
-		}
+    use ink_storage::traits::SpreadAllocate;
+    use openbrush::{
+        contracts::ownable::*,
+        traits::{
+            Storage,
+            ZERO_ADDRESS,
+        },
+    };
+    use pair_contract::pair::PairContractRef;
+    use uniswap_v2::{
+        impls::factory::*,
+        traits::factory::*,
+    };
+
+    #[ink(event)]
+    pub struct OwnerChanged {
+        #[ink(topic)]
+        pub old_owner: AccountId,
+        #[ink(topic)]
+        pub new_owner: AccountId,
+    }
 
     #[ink(event)]
     pub struct PoolCreated {
-			#[ink(topic)]
-			pub token_0: AccountId,
-			#[ink(topic)]
-			pub token_1: AccountId,
-			pub fee: u8,
-			pub tickspacing: i8,
-			pub pool: AccountId,
+        #[ink(topic)]
+        pub token_0: AccountId,
+        #[ink(topic)]
+        pub token_1: AccountId,
+        #[ink(topic)]
+        pub fee: u32,
+        pub tick_spacing: i32,
+        pub pool: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct FeeAmountEnabled {
+        #[ink(topic)]
+        pub fee: u32,
+        #[ink(topic)]
+        pub tick_spacing: i32,
     }
-		#[ink(event)]
-		pub struct FeeAmountEnabled {
-			#[ink(topic)]
-			pub fee: u8,
-			
-			#[ink(topic)]
-			pub tickspacing: i8,
-		}
+
+    #[ink(event)]
+    pub struct PoolCodeHashSet {
+        pub pool_code_hash: Hash,
+    }
+
+    #[ink(event)]
+    pub struct PoolMigrated {
+        #[ink(topic)]
+        pub pool: AccountId,
+        pub new_code_hash: Hash,
+    }
+
     #[ink(storage)]
     #[derive(Default, SpreadAllocate, Storage)]
     pub struct FactoryContract {
+        #[storage_field]
+        ownable: ownable::Data,
         #[storage_field]
         factory: data::Data,
     }
 
     impl Factory for FactoryContract {}
 
+    /// Ownership is only changed through `Factory::set_owner`, Ownable's messages are not exposed.
+    impl ownable::Internal for FactoryContract {
+        fn _emit_ownership_transferred_event(
+            &self,
+            previous_owner: Option<AccountId>,
+            new_owner: Option<AccountId>,
+        ) {
+            self._emit_owner_changed_event(
+                previous_owner.unwrap_or_else(|| ZERO_ADDRESS.into()),
+                new_owner.unwrap_or_else(|| ZERO_ADDRESS.into()),
+            )
+        }
+    }
+
     impl factory::Internal for FactoryContract {
         fn _instantiate_pool(&mut self, salt_bytes: &[u8]) -> Result<AccountId, FactoryError> {
-            let pair_hash = self.factory.pair_contract_code_hash;
+            let pool_code_hash = self.factory.pool_code_hash;
             let pair = PairContractRef::new()
                 .endowment(0)
-                .code_hash(pair_hash)
-                .salt_bytes(&salt_bytes[..4])
+                .code_hash(pool_code_hash)
+                .salt_bytes(salt_bytes)
                 .instantiate()
                 .map_err(|_| FactoryError::PairInstantiationFailed)?;
             Ok(pair.to_account_id())
@@ -62,9 +107,9 @@ mod factory {
             &self,
             token_0: AccountId,
             token_1: AccountId,
-						fee: u8,
-						tick_spacing: i8,
-						pool: AccountId,
+            fee: u32,
+            tick_spacing: i32,
+            pool: AccountId,
         ) {
             EmitEvent::<FactoryContract>::emit_event(
                 self.env(),
@@ -73,7 +118,38 @@ mod factory {
                     token_1,
                     fee,
                     tick_spacing,
-										pool
+                    pool,
+                },
+            )
+        }
+
+        fn _emit_fee_amount_enabled_event(&self, fee: u32, tick_spacing: i32) {
+            EmitEvent::<FactoryContract>::emit_event(
+                self.env(),
+                FeeAmountEnabled { fee, tick_spacing },
+            )
+        }
+
+        fn _emit_owner_changed_event(&self, old_owner: AccountId, new_owner: AccountId) {
+            EmitEvent::<FactoryContract>::emit_event(
+                self.env(),
+                OwnerChanged {
+                    old_owner,
+                    new_owner,
+                },
+            )
+        }
+
+        fn _emit_pool_code_hash_set_event(&self, pool_code_hash: Hash) {
+            EmitEvent::<FactoryContract>::emit_event(self.env(), PoolCodeHashSet { pool_code_hash })
+        }
+
+        fn _emit_pool_migrated_event(&self, pool: AccountId, new_code_hash: Hash) {
+            EmitEvent::<FactoryContract>::emit_event(
+                self.env(),
+                PoolMigrated {
+                    pool,
+                    new_code_hash,
                 },
             )
         }
@@ -81,42 +157,129 @@ mod factory {
 
     impl FactoryContract {
         #[ink(constructor)]
-        pub fn new(fee_to_setter: AccountId, pair_code_hash: Hash) -> Self {
+        pub fn new(pool_code_hash: Hash) -> Self {
             ink_lang::codegen::initialize_contract(|instance: &mut Self| {
-                instance.factory.pair_contract_code_hash = pair_code_hash;
-								let caller = Self::env().caller();
-								fn _emit_owner_changed(
-									&self,
-									old_owner: AccountId,
-									new_owner: AccountId
-							) {
-									EmitEvent::<FactoryContract>::emit_event(
-											self.env(),
-											OwnerChanged {
-												old_owner, //ZeroAccount
-												caller
-											},
-									)
-							}
-							// TODO feeamountenabled event
+                instance.factory.pool_code_hash = pool_code_hash;
+                let caller = instance.env().caller();
+                instance._init_with_owner(caller);
+                // default fee tiers in hundredths of a bip: 0.01%, 0.05%, 0.3% and 1%
+                for (fee, tick_spacing) in [(100, 1), (500, 10), (3000, 60), (10000, 200)] {
+                    instance
+                        .factory
+                        .fee_amount_tick_spacing
+                        .insert(&fee, &tick_spacing);
+                    instance._emit_fee_amount_enabled_event(fee, tick_spacing);
+                }
             })
         }
     }
     #[cfg(test)]
     mod tests {
+        use super::*;
         use ink_env::{
             test::default_accounts,
             Hash,
         };
-        use openbrush::traits::AccountIdExt;
-
-        use super::*;
 
         #[ink_lang::test]
         fn initialize_works() {
             let accounts = default_accounts::<ink_env::DefaultEnvironment>();
-            let factory = FactoryContract::new(accounts.alice, Hash::default());
-            assert!(factory.factory.fee_to.is_zero());
+            let factory = FactoryContract::new(Hash::default());
+            assert_eq!(Factory::owner(&factory), accounts.alice);
+        }
+
+        #[ink_lang::test]
+        fn new_enables_default_fee_tiers() {
+            let factory = FactoryContract::new(Hash::default());
+            assert_eq!(factory.fee_amount_tick_spacing(100), 1);
+            assert_eq!(factory.fee_amount_tick_spacing(500), 10);
+            assert_eq!(factory.fee_amount_tick_spacing(3000), 60);
+            assert_eq!(factory.fee_amount_tick_spacing(10000), 200);
+            assert_eq!(factory.fee_amount_tick_spacing(1000), 0);
+        }
+
+        #[ink_lang::test]
+        fn enable_fee_amount_works() {
+            let mut factory = FactoryContract::new(Hash::default());
+            assert_eq!(factory.enable_fee_amount(1000, 20), Ok(()));
+            assert_eq!(factory.fee_amount_tick_spacing(1000), 20);
+            assert_eq!(
+                factory.enable_fee_amount(1000, 30),
+                Err(FactoryError::FeeAmountAlreadyEnabled)
+            );
+            assert_eq!(
+                factory.enable_fee_amount(2000, 0),
+                Err(FactoryError::InvalidTickSpacing)
+            );
+            assert_eq!(
+                factory.enable_fee_amount(2000, 16385),
+                Err(FactoryError::InvalidTickSpacing)
+            );
+            assert_eq!(factory.enable_fee_amount(2000, 16384), Ok(()));
+        }
+
+        #[ink_lang::test]
+        fn set_pool_code_hash_works() {
+            let mut factory = FactoryContract::new(Hash::default());
+            let pool_code_hash = Hash::from([0x01; 32]);
+            assert_eq!(factory.set_pool_code_hash(pool_code_hash), Ok(()));
+            assert_eq!(factory.pool_code_hash(), pool_code_hash);
+
+            let emitted = ink_env::test::recorded_events().any(|event| {
+                matches!(
+                    <Event as scale::Decode>::decode(&mut &event.data[..]),
+                    Ok(Event::PoolCodeHashSet(PoolCodeHashSet { pool_code_hash: hash }))
+                        if hash == pool_code_hash
+                )
+            });
+            assert!(emitted);
+        }
+
+        #[ink_lang::test]
+        fn set_owner_works_only_for_owner() {
+            let accounts = default_accounts::<ink_env::DefaultEnvironment>();
+            let mut factory = FactoryContract::new(Hash::default());
+            assert_eq!(factory.set_owner(accounts.bob), Ok(()));
+            assert_eq!(Factory::owner(&factory), accounts.bob);
+
+            assert_eq!(
+                factory.set_owner(accounts.alice),
+                Err(FactoryError::OwnableError(OwnableError::CallerIsNotOwner))
+            );
+            assert_eq!(
+                factory.enable_fee_amount(1000, 20),
+                Err(FactoryError::OwnableError(OwnableError::CallerIsNotOwner))
+            );
+            assert_eq!(
+                factory.set_pool_code_hash(Hash::default()),
+                Err(FactoryError::OwnableError(OwnableError::CallerIsNotOwner))
+            );
+        }
+
+        #[ink_lang::test]
+        fn set_owner_emits_owner_changed() {
+            let accounts = default_accounts::<ink_env::DefaultEnvironment>();
+            let mut factory = FactoryContract::new(Hash::default());
+            assert_eq!(factory.set_owner(accounts.bob), Ok(()));
+
+            let owner_changes: Vec<(AccountId, AccountId)> = ink_env::test::recorded_events()
+                .filter_map(
+                    |event| match <Event as scale::Decode>::decode(&mut &event.data[..]) {
+                        Ok(Event::OwnerChanged(OwnerChanged {
+                            old_owner,
+                            new_owner,
+                        })) => Some((old_owner, new_owner)),
+                        _ => None,
+                    },
+                )
+                .collect();
+            assert_eq!(
+                owner_changes,
+                [
+                    (AccountId::from(ZERO_ADDRESS), accounts.alice),
+                    (accounts.alice, accounts.bob)
+                ]
+            );
         }
     }
 }
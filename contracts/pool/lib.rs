@@ -7,25 +7,31 @@ pub mod pair {
         EmitEvent,
         Env,
     };
-    use ink_prelude::vec::Vec;
     use ink_storage::traits::SpreadAllocate;
     use openbrush::{
-        contracts::{
-            ownable::*,
-            psp22::*,
-            reentrancy_guard,
-        },
+        contracts::ownable::*,
         traits::Storage,
     };
     use uniswap_v2::{
-        ensure,
         impls::pair::*,
-        traits::pair::*,
+        traits::{
+            pair::*,
+            types::WrappedU256,
+        },
     };
 
 		/////////////////////////////////////////////////////////////////////////////////////////////////////
 		// poolEvent
 		
+    /// Emitted exactly once by a pool when #initialize is first called on the pool
+    /// sqrtPriceX96 The initial sqrt price of the pool, as a Q64.96
+    /// tick The initial tick of the pool, i.e. log base 1.0001 of the starting price of the pool
+    #[ink(event)]
+    pub struct Initialize {
+        pub sqrt_price_x96: WrappedU256,
+        pub tick: i32,
+    }
+
     /// Emitted when liquidity is minted for a given position
     /// sender The address that minted the liquidity
     /// owner The owner of the position and recipient of any minted liquidity
@@ -37,11 +43,14 @@ pub mod pair {
 		
     #[ink(event)]
     pub struct Mint {
-        #[ink(topic)]
         pub sender: AccountId,
-				pub tick_lower: u8,
-				pub tick_upper: u8,
-				pub amount: Balance,
+        #[ink(topic)]
+        pub owner: AccountId,
+        #[ink(topic)]
+        pub tick_lower: i32,
+        #[ink(topic)]
+        pub tick_upper: i32,
+        pub amount: u128,
         pub amount_0: Balance,
         pub amount_1: Balance,
     }
@@ -54,15 +63,17 @@ pub mod pair {
     /// @param amount0 The amount of token0 fees collected
     /// @param amount1 The amount of token1 fees collected
     #[ink(event)]
-		pub struct Collect {
-			#[ink(topic)]
-			pub owner: AccountId,
-			pub recipient: AccountId,
-			pub tick_lower: u8,
-			pub tick_upper: u8,
-			pub amount_0: Balance,
-			pub amount_1: Balance,
-		}
+    pub struct Collect {
+        #[ink(topic)]
+        pub owner: AccountId,
+        pub recipient: AccountId,
+        #[ink(topic)]
+        pub tick_lower: i32,
+        #[ink(topic)]
+        pub tick_upper: i32,
+        pub amount_0: Balance,
+        pub amount_1: Balance,
+    }
     /// @notice Emitted when a position's liquidity is removed
     /// @dev Does not withdraw any fees earned by the liquidity position, which must be withdrawn via #collect
     /// @param owner The owner of the position for which liquidity is removed
@@ -74,10 +85,12 @@ pub mod pair {
     #[ink(event)]
     pub struct Burn {
         #[ink(topic)]
-        pub sender: AccountId,
-				pub tick_lower: u8,
-				pub tick_upper: u8,
-				pub amount: Balance,
+        pub owner: AccountId,
+        #[ink(topic)]
+        pub tick_lower: i32,
+        #[ink(topic)]
+        pub tick_upper: i32,
+        pub amount: u128,
         pub amount_0: Balance,
         pub amount_1: Balance,
     }
@@ -93,12 +106,13 @@ pub mod pair {
     pub struct Swap {
         #[ink(topic)]
         pub sender: AccountId,
-				pub recipient: AccountId,
-				pub amount_0: Balance,
-				pub amount_1: Balance,
-				//TODO pub sqrtPriceX96: u128, 
-				pub liquidity: u128,
-				pub tick: u8,
+        #[ink(topic)]
+        pub recipient: AccountId,
+        pub amount_0: i128,
+        pub amount_1: i128,
+        pub sqrt_price_x96: WrappedU256,
+        pub liquidity: u128,
+        pub tick: i32,
     }
     /// @notice Emitted by the pool for any flashes of token0/token1
     /// @param sender The address that initiated the swap call, and that received the callback
@@ -107,219 +121,212 @@ pub mod pair {
     /// @param amount1 The amount of token1 that was flashed
     /// @param paid0 The amount of token0 paid for the flash, which can exceed the amount0 plus the fee
     /// @param paid1 The amount of token1 paid for the flash, which can exceed the amount1 plus the fee
-		#[ink(event)]
-		pub struct Flash {
-			#[ink(topic)]
-			pub sender : AccountId,
-			pub recipient: AccountId,
-			pub amount0: Balance,
-			pub amount1: Balance,
-			pub paid0: Balance,
-			pub paid1: Balance,
-		}
+    #[ink(event)]
+    pub struct Flash {
+        #[ink(topic)]
+        pub sender: AccountId,
+        #[ink(topic)]
+        pub recipient: AccountId,
+        pub amount_0: Balance,
+        pub amount_1: Balance,
+        pub paid_0: Balance,
+        pub paid_1: Balance,
+    }
     /// @notice Emitted by the pool for increases to the number of observations that can be stored
     /// @dev observationCardinalityNext is not the observation cardinality until an observation is written at the index
     /// just before a mint/swap/burn.
     /// @param observationCardinalityNextOld The previous value of the next observation cardinality
     /// @param observationCardinalityNextNew The updated value of the next observation cardinality
-		#[ink(event)]
-    pub struct IncreaseObservationCardinalityNext(
-			pub observation_cardinality_next_old: u8,
-			pub observation_cardinality_next_new: u8,
-	);
+    #[ink(event)]
+    pub struct IncreaseObservationCardinalityNext {
+        pub observation_cardinality_next_old: u16,
+        pub observation_cardinality_next_new: u16,
+    }
 
 	/// @notice Emitted when the protocol fee is changed by the pool
 	/// @param feeProtocol0Old The previous value of the token0 protocol fee
 	/// @param feeProtocol1Old The previous value of the token1 protocol fee
 	/// @param feeProtocol0New The updated value of the token0 protocol fee
 	/// @param feeProtocol1New The updated value of the token1 protocol fee
-	#[ink(event)]
-	pub struct SetFeeProtocol{
-		pub fee_protocol0_old: u8,
-		pub fee_protocol1_old: u8,
-		pub fee_protocol0_new: u8,
-		pub fee_protocol1_new: u8,
-	}
+    #[ink(event)]
+    pub struct SetFeeProtocol {
+        pub fee_protocol_0_old: u8,
+        pub fee_protocol_1_old: u8,
+        pub fee_protocol_0_new: u8,
+        pub fee_protocol_1_new: u8,
+    }
 
 	/// @notice Emitted when the collected protocol fees are withdrawn by the factory owner
 	/// @param sender The address that collects the protocol fees
 	/// @param recipient The address that receives the collected protocol fees
 	/// @param amount0 The amount of token0 protocol fees that is withdrawn
 	/// @param amount0 The amount of token1 protocol fees that is withdrawn
-	#[ink(event)]
-	pub struct CollectProtocol{
-		pub sender: AccountId, 
-		pub recipient: AccountId, 
-		pub amount0: Balance, 
-		pub amount1: Balance, 
-	}
-	/////////////////////////////////////////////////////////////////////////////////////////////////////
     #[ink(event)]
-    pub struct Sync {
-        reserve_0: Balance,
-        reserve_1: Balance,
-    }
-
-    #[ink(event)]
-    pub struct Transfer {
+    pub struct CollectProtocol {
         #[ink(topic)]
-        from: Option<AccountId>,
+        pub sender: AccountId,
         #[ink(topic)]
-        to: Option<AccountId>,
-        value: Balance,
+        pub recipient: AccountId,
+        pub amount_0: Balance,
+        pub amount_1: Balance,
     }
-
+	/////////////////////////////////////////////////////////////////////////////////////////////////////
     #[ink(storage)]
     #[derive(Default, SpreadAllocate, Storage)]
     pub struct PairContract {
-        #[storage_field]
-        psp22: psp22::Data,
         #[storage_field]
         ownable: ownable::Data,
         #[storage_field]
-        guard: reentrancy_guard::Data,
-        #[storage_field]
         pair: data::Data,
     }
 
-    impl PSP22 for PairContract {
-        #[ink(message)]
-        fn transfer_from(
-            &mut self,
-            from: AccountId,
-            to: AccountId,
-            value: Balance,
-            data: Vec<u8>,
-        ) -> Result<(), PSP22Error> {
-            let caller = self.env().caller();
-            let allowance = self._allowance(&from, &caller);
-
-            // In uniswapv2 max allowance never decrease
-            if allowance != u128::MAX {
-                ensure!(allowance >= value, PSP22Error::InsufficientAllowance);
-                self._approve_from_to(from, caller, allowance - value)?;
-            }
-            self._transfer_from_to(from, to, value, data)?;
-            Ok(())
-        }
-    }
-
-    impl psp22::Internal for PairContract {
-        // in uniswapv2 no check for zero account
-        fn _mint_to(&mut self, account: AccountId, amount: Balance) -> Result<(), PSP22Error> {
-            let mut new_balance = self._balance_of(&account);
-            new_balance += amount;
-            self.psp22.balances.insert(&account, &new_balance);
-            self.psp22.supply += amount;
-            self._emit_transfer_event(None, Some(account), amount);
-            Ok(())
-        }
-
-        fn _burn_from(&mut self, account: AccountId, amount: Balance) -> Result<(), PSP22Error> {
-            let mut from_balance = self._balance_of(&account);
-
-            ensure!(from_balance >= amount, PSP22Error::InsufficientBalance);
+    impl Ownable for PairContract {}
 
-            from_balance -= amount;
-            self.psp22.balances.insert(&account, &from_balance);
-            self.psp22.supply -= amount;
-            self._emit_transfer_event(Some(account), None, amount);
-            Ok(())
+    impl pair::Internal for PairContract {
+        fn _emit_initialize_event(&self, sqrt_price_x96: WrappedU256, tick: i32) {
+            self.env().emit_event(Initialize {
+                sqrt_price_x96,
+                tick,
+            })
         }
 
-        fn _approve_from_to(
-            &mut self,
+        fn _emit_mint_event(
+            &self,
+            sender: AccountId,
             owner: AccountId,
-            spender: AccountId,
-            amount: Balance,
-        ) -> Result<(), PSP22Error> {
-            self.psp22.allowances.insert(&(&owner, &spender), &amount);
-            self._emit_approval_event(owner, spender, amount);
-            Ok(())
+            tick_lower: i32,
+            tick_upper: i32,
+            amount: u128,
+            amount_0: Balance,
+            amount_1: Balance,
+        ) {
+            self.env().emit_event(Mint {
+                sender,
+                owner,
+                tick_lower,
+                tick_upper,
+                amount,
+                amount_0,
+                amount_1,
+            })
         }
 
-        fn _transfer_from_to(
-            &mut self,
-            from: AccountId,
-            to: AccountId,
-            amount: Balance,
-            _data: Vec<u8>,
-        ) -> Result<(), PSP22Error> {
-            let from_balance = self._balance_of(&from);
-
-            ensure!(from_balance >= amount, PSP22Error::InsufficientBalance);
-
-            self.psp22.balances.insert(&from, &(from_balance - amount));
-            let to_balance = self._balance_of(&to);
-            self.psp22.balances.insert(&to, &(to_balance + amount));
-
-            self._emit_transfer_event(Some(from), Some(to), amount);
-            Ok(())
+        fn _emit_burn_event(
+            &self,
+            owner: AccountId,
+            tick_lower: i32,
+            tick_upper: i32,
+            amount: u128,
+            amount_0: Balance,
+            amount_1: Balance,
+        ) {
+            self.env().emit_event(Burn {
+                owner,
+                tick_lower,
+                tick_upper,
+                amount,
+                amount_0,
+                amount_1,
+            })
         }
 
-        fn _emit_transfer_event(
+        fn _emit_collect_event(
             &self,
-            from: Option<AccountId>,
-            to: Option<AccountId>,
-            amount: Balance,
+            owner: AccountId,
+            recipient: AccountId,
+            tick_lower: i32,
+            tick_upper: i32,
+            amount_0: Balance,
+            amount_1: Balance,
         ) {
-            self.env().emit_event(Transfer {
-                from,
-                to,
-                value: amount,
-            });
+            self.env().emit_event(Collect {
+                owner,
+                recipient,
+                tick_lower,
+                tick_upper,
+                amount_0,
+                amount_1,
+            })
         }
-    }
-
-    impl Ownable for PairContract {}
 
-    impl pair::Internal for PairContract {
-        fn _emit_mint_event(&self, sender: AccountId, amount_0: Balance, amount_1: Balance) {
-            self.env().emit_event(Mint {
+        fn _emit_swap_event(
+            &self,
+            sender: AccountId,
+            recipient: AccountId,
+            amount_0: i128,
+            amount_1: i128,
+            sqrt_price_x96: WrappedU256,
+            liquidity: u128,
+            tick: i32,
+        ) {
+            self.env().emit_event(Swap {
                 sender,
+                recipient,
                 amount_0,
                 amount_1,
+                sqrt_price_x96,
+                liquidity,
+                tick,
             })
         }
 
-        fn _emit_burn_event(
+        fn _emit_flash_event(
             &self,
             sender: AccountId,
+            recipient: AccountId,
             amount_0: Balance,
             amount_1: Balance,
-            to: AccountId,
+            paid_0: Balance,
+            paid_1: Balance,
         ) {
-            self.env().emit_event(Burn {
+            self.env().emit_event(Flash {
                 sender,
+                recipient,
                 amount_0,
                 amount_1,
-                to,
+                paid_0,
+                paid_1,
             })
         }
 
-        fn _emit_swap_event(
+        fn _emit_increase_observation_cardinality_next_event(
             &self,
-            sender: AccountId,
-            amount_0_in: Balance,
-            amount_1_in: Balance,
-            amount_0_out: Balance,
-            amount_1_out: Balance,
-            to: AccountId,
+            observation_cardinality_next_old: u16,
+            observation_cardinality_next_new: u16,
         ) {
-            self.env().emit_event(Swap {
-                sender,
-                amount_0_in,
-                amount_1_in,
-                amount_0_out,
-                amount_1_out,
-                to,
+            self.env().emit_event(IncreaseObservationCardinalityNext {
+                observation_cardinality_next_old,
+                observation_cardinality_next_new,
+            })
+        }
+
+        fn _emit_set_fee_protocol_event(
+            &self,
+            fee_protocol_0_old: u8,
+            fee_protocol_1_old: u8,
+            fee_protocol_0_new: u8,
+            fee_protocol_1_new: u8,
+        ) {
+            self.env().emit_event(SetFeeProtocol {
+                fee_protocol_0_old,
+                fee_protocol_1_old,
+                fee_protocol_0_new,
+                fee_protocol_1_new,
             })
         }
 
-        fn _emit_sync_event(&self, reserve_0: Balance, reserve_1: Balance) {
-            self.env().emit_event(Sync {
-                reserve_0,
-                reserve_1,
+        fn _emit_collect_protocol_event(
+            &self,
+            sender: AccountId,
+            recipient: AccountId,
+            amount_0: Balance,
+            amount_1: Balance,
+        ) {
+            self.env().emit_event(CollectProtocol {
+                sender,
+                recipient,
+                amount_0,
+                amount_1,
             })
         }
     }
@@ -339,15 +346,72 @@ pub mod pair {
     #[cfg(test)]
     mod tests {
         use ink_env::AccountId;
+        use uniswap_v2::helpers::tick_math::get_sqrt_ratio_at_tick;
 
         use super::*;
 
         #[ink_lang::test]
-        fn initialize_works() {
+        fn setup_works_once() {
             let mut pair = PairContract::new();
             let token_0 = AccountId::from([0x03; 32]);
             let token_1 = AccountId::from([0x04; 32]);
-            assert_eq!(pair.initialize(token_0, token_1), Ok(()));
+            assert_eq!(
+                pair.setup(token_0, token_1, 3000, 0),
+                Err(PairError::InvalidTickSpacing)
+            );
+            assert_eq!(pair.setup(token_0, token_1, 3000, 60), Ok(()));
+            assert_eq!(pair.tick_spacing(), 60);
+            assert_eq!(
+                pair.max_liquidity_per_tick(),
+                tick::tick_spacing_to_max_liquidity_per_tick(60)
+            );
+
+            assert_eq!(
+                pair.setup(token_0, token_1, 500, 10),
+                Err(PairError::AlreadySetUp)
+            );
+            assert_eq!(pair.tick_spacing(), 60);
+        }
+
+        #[ink_lang::test]
+        fn initialize_works_once() {
+            let mut pair = PairContract::new();
+            // price of 1 token1 per token0
+            let sqrt_price_x96: WrappedU256 = get_sqrt_ratio_at_tick(0).unwrap().into();
+            assert_eq!(pair.initialize(sqrt_price_x96), Err(PairError::NotSetUp));
+
+            let token_0 = AccountId::from([0x03; 32]);
+            let token_1 = AccountId::from([0x04; 32]);
+            assert_eq!(pair.setup(token_0, token_1, 3000, 60), Ok(()));
+            assert_eq!(pair.initialize(sqrt_price_x96), Ok(()));
+
+            let slot0 = pair.slot0();
+            assert_eq!(slot0.sqrt_price_x96, sqrt_price_x96);
+            assert_eq!(slot0.tick, 0);
+            assert_eq!(slot0.observation_cardinality, 1);
+            assert!(slot0.unlocked);
+
+            assert_eq!(
+                pair.initialize(sqrt_price_x96),
+                Err(PairError::AlreadyInitialized)
+            );
+        }
+
+        /// Only the rejected path is covered: the off-chain environment does not implement
+        /// `ink_env::set_code_hash` and panics, so the version bump of a successful migration
+        /// can only be checked on-chain.
+        #[ink_lang::test]
+        fn set_code_hash_only_by_factory() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut pair = PairContract::new();
+            assert_eq!(pair.version(), 0);
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                pair.set_code_hash(Hash::from([0x01; 32])),
+                Err(PairError::OwnableError(OwnableError::CallerIsNotOwner))
+            );
+            assert_eq!(pair.version(), 0);
         }
     }
 }
\ No newline at end of file
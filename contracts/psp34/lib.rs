@@ -1,74 +1,294 @@
 #![cfg_attr(not(feature = "std"), no_std)]
+#![feature(min_specialization)]
 
-use ink_lang as ink;
+#[openbrush::contract]
+pub mod position_manager {
+    use ink_lang::codegen::{
+        EmitEvent,
+        Env,
+    };
+    use ink_prelude::{
+        string::String,
+        vec::Vec,
+    };
+    use ink_storage::traits::SpreadAllocate;
+    use openbrush::{
+        contracts::psp34::{
+            extensions::metadata::PSP34Metadata,
+            *,
+        },
+        traits::Storage,
+    };
+    use uniswap_v2::{
+        impls::position_manager::{
+            descriptor::TOKEN_URI_KEY,
+            *,
+        },
+        traits::{
+            callback::*,
+            position_manager::*,
+        },
+    };
 
-#[ink::contract]
-mod psp34 {
+    #[ink(event)]
+    pub struct Transfer {
+        #[ink(topic)]
+        from: Option<AccountId>,
+        #[ink(topic)]
+        to: Option<AccountId>,
+        #[ink(topic)]
+        id: Id,
+    }
+
+    #[ink(event)]
+    pub struct Approval {
+        #[ink(topic)]
+        from: AccountId,
+        #[ink(topic)]
+        to: AccountId,
+        #[ink(topic)]
+        id: Option<Id>,
+        approved: bool,
+    }
+
+    /// Emitted when liquidity is increased for a position NFT, also when the token is minted.
+    #[ink(event)]
+    pub struct IncreaseLiquidity {
+        #[ink(topic)]
+        pub token_id: Id,
+        pub liquidity: u128,
+        pub amount_0: Balance,
+        pub amount_1: Balance,
+    }
+
+    /// Emitted when liquidity is decreased for a position NFT.
+    #[ink(event)]
+    pub struct DecreaseLiquidity {
+        #[ink(topic)]
+        pub token_id: Id,
+        pub liquidity: u128,
+        pub amount_0: Balance,
+        pub amount_1: Balance,
+    }
+
+    /// Emitted when tokens are collected for a position NFT.
+    ///
+    /// The amounts reported may not be exactly equivalent to the amounts transferred, due to rounding behavior.
+    #[ink(event)]
+    pub struct Collect {
+        #[ink(topic)]
+        pub token_id: Id,
+        pub recipient: AccountId,
+        pub amount_0: Balance,
+        pub amount_1: Balance,
+    }
 
-    /// Defines the storage of your contract.
-    /// Add new fields to the below struct in order
-    /// to add new static storage fields to your contract.
     #[ink(storage)]
-    pub struct Psp34 {
-        /// Stores a single `bool` value on the storage.
-        value: bool,
+    #[derive(Default, SpreadAllocate, Storage)]
+    pub struct PositionManagerContract {
+        #[storage_field]
+        psp34: psp34::Data,
+        #[storage_field]
+        manager: data::Data,
     }
 
-    impl Psp34 {
-        /// Constructor that initializes the `bool` value to the given `init_value`.
-        #[ink(constructor)]
-        pub fn new(init_value: bool) -> Self {
-            Self { value: init_value }
+    impl PSP34 for PositionManagerContract {}
+
+    impl psp34::Internal for PositionManagerContract {
+        fn _emit_transfer_event(&self, from: Option<AccountId>, to: Option<AccountId>, id: Id) {
+            self.env().emit_event(Transfer { from, to, id });
         }
 
-        /// Constructor that initializes the `bool` value to `false`.
-        ///
-        /// Constructors can delegate to other constructors.
-        #[ink(constructor)]
-        pub fn default() -> Self {
-            Self::new(Default::default())
+        fn _emit_approval_event(
+            &self,
+            from: AccountId,
+            to: AccountId,
+            id: Option<Id>,
+            approved: bool,
+        ) {
+            self.env().emit_event(Approval {
+                from,
+                to,
+                id,
+                approved,
+            });
         }
+    }
 
-        /// A message that can be called on instantiated contracts.
-        /// This one flips the value of the stored `bool` from `true`
-        /// to `false` and vice versa.
+    impl PSP34Metadata for PositionManagerContract {
+        /// Only the `token_uri` attribute is supported, it is generated from the position on every call.
         #[ink(message)]
-        pub fn flip(&mut self) {
-            self.value = !self.value;
+        fn get_attribute(&self, id: Id, key: Vec<u8>) -> Option<Vec<u8>> {
+            if key != TOKEN_URI_KEY {
+                return None
+            }
+            self.token_uri(id).ok().map(String::into_bytes)
         }
+    }
 
-        /// Simply returns the current value of our `bool`.
-        #[ink(message)]
-        pub fn get(&self) -> bool {
-            self.value
+    impl MintCallback for PositionManagerContract {}
+
+    impl PositionManager for PositionManagerContract {}
+
+    impl position_manager::Internal for PositionManagerContract {
+        fn _emit_increase_liquidity_event(
+            &self,
+            token_id: Id,
+            liquidity: u128,
+            amount_0: Balance,
+            amount_1: Balance,
+        ) {
+            self.env().emit_event(IncreaseLiquidity {
+                token_id,
+                liquidity,
+                amount_0,
+                amount_1,
+            })
+        }
+
+        fn _emit_decrease_liquidity_event(
+            &self,
+            token_id: Id,
+            liquidity: u128,
+            amount_0: Balance,
+            amount_1: Balance,
+        ) {
+            self.env().emit_event(DecreaseLiquidity {
+                token_id,
+                liquidity,
+                amount_0,
+                amount_1,
+            })
+        }
+
+        fn _emit_collect_event(
+            &self,
+            token_id: Id,
+            recipient: AccountId,
+            amount_0: Balance,
+            amount_1: Balance,
+        ) {
+            self.env().emit_event(Collect {
+                token_id,
+                recipient,
+                amount_0,
+                amount_1,
+            })
+        }
+    }
+
+    impl PositionManagerContract {
+        #[ink(constructor)]
+        pub fn new(factory: AccountId) -> Self {
+            ink_lang::codegen::initialize_contract(|instance: &mut Self| {
+                instance.manager.factory = factory;
+            })
         }
     }
 
-    /// Unit tests in Rust are normally defined within such a `#[cfg(test)]`
-    /// module and test functions are marked with a `#[test]` attribute.
-    /// The below code is technically just normal Rust code.
     #[cfg(test)]
     mod tests {
-        /// Imports all the definitions from the outer scope so we can use them here.
+        use ink_env::AccountId;
+        use uniswap_v2::traits::{
+            router::RouterError,
+            types::WrappedU256,
+        };
+
         use super::*;
 
-        /// Imports `ink_lang` so we can use `#[ink::test]`.
-        use ink_lang as ink;
+        #[ink_lang::test]
+        fn new_works() {
+            let factory = AccountId::from([0x01; 32]);
+            let manager = PositionManagerContract::new(factory);
+            assert_eq!(manager.factory(), factory);
+            assert_eq!(manager.positions(Id::U128(1)), None);
+            assert_eq!(manager.total_supply(), 0);
+        }
+
+        #[ink_lang::test]
+        fn mint_rejects_unsorted_tokens() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut manager = PositionManagerContract::new(AccountId::from([0x01; 32]));
+            let params = MintParams {
+                token_0: AccountId::from([0x04; 32]),
+                token_1: AccountId::from([0x03; 32]),
+                fee: 3000,
+                tick_lower: -60,
+                tick_upper: 60,
+                amount_0_desired: 100,
+                amount_1_desired: 100,
+                amount_0_min: 0,
+                amount_1_min: 0,
+                recipient: accounts.alice,
+                sqrt_price_x96: WrappedU256::default(),
+            };
+            assert_eq!(
+                manager.mint(params),
+                Err(PositionManagerError::TokensNotSorted)
+            );
+        }
+
+        #[ink_lang::test]
+        fn increase_and_decrease_liquidity_check_deadline() {
+            let mut manager = PositionManagerContract::new(AccountId::from([0x01; 32]));
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(
+                manager.increase_liquidity(Id::U128(1), 100, 100, 0, 0, 0),
+                Err(PositionManagerError::RouterError(RouterError::Expired))
+            );
+            assert_eq!(
+                manager.decrease_liquidity(Id::U128(1), 100, 0, 0, 0),
+                Err(PositionManagerError::RouterError(RouterError::Expired))
+            );
+            assert_eq!(
+                manager.decrease_liquidity(Id::U128(1), 100, 0, 0, u64::MAX),
+                Err(PositionManagerError::TokenNotFound)
+            );
+        }
+
+        #[ink_lang::test]
+        fn burn_works_only_for_approved_cleared_position() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut manager = PositionManagerContract::new(AccountId::from([0x01; 32]));
+            let token_id = Id::U128(1);
+            psp34::Internal::_mint_to(&mut manager, accounts.alice, token_id.clone()).unwrap();
+            let mut position = data::Position {
+                liquidity: 100,
+                ..Default::default()
+            };
+            manager.manager.positions.insert(&token_id, &position);
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(
+                manager.burn(token_id.clone()),
+                Err(PositionManagerError::NotApproved)
+            );
+
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(
+                manager.burn(token_id.clone()),
+                Err(PositionManagerError::NotCleared)
+            );
 
-        /// We test if the default constructor does its job.
-        #[ink::test]
-        fn default_works() {
-            let psp34 = Psp34::default();
-            assert_eq!(psp34.get(), false);
+            position.liquidity = 0;
+            manager.manager.positions.insert(&token_id, &position);
+            assert_eq!(manager.burn(token_id.clone()), Ok(()));
+            assert_eq!(manager.positions(token_id.clone()), None);
+            assert_eq!(manager.owner_of(token_id), None);
         }
 
-        /// We test a simple use case of our contract.
-        #[ink::test]
-        fn it_works() {
-            let mut psp34 = Psp34::new(false);
-            assert_eq!(psp34.get(), false);
-            psp34.flip();
-            assert_eq!(psp34.get(), true);
+        #[ink_lang::test]
+        fn get_attribute_supports_only_token_uri() {
+            let manager = PositionManagerContract::new(AccountId::from([0x01; 32]));
+            assert_eq!(
+                manager.token_uri(Id::U128(1)),
+                Err(PositionManagerError::TokenNotFound)
+            );
+            assert_eq!(
+                manager.get_attribute(Id::U128(1), TOKEN_URI_KEY.to_vec()),
+                None
+            );
+            assert_eq!(manager.get_attribute(Id::U128(1), b"name".to_vec()), None);
         }
     }
 }
@@ -0,0 +1,131 @@
+use crate::{
+    ensure,
+    helpers::math::MathError,
+};
+use primitive_types::{
+    U256,
+    U512,
+};
+
+/// 2**128, the denominator of Q128.128 fixed point values such as fee growth.
+pub const Q128: U256 = U256([0, 0, 1, 0]);
+
+/// Calculates floor(a×b÷denominator) with full precision.
+///
+/// The product is computed over a 512-bit intermediate so that it never overflows,
+/// only a result which does not fit in 256 bits is rejected.
+/// Please refer https://github.com/Uniswap/v3-core/blob/main/contracts/libraries/FullMath.sol
+pub fn mul_div(a: U256, b: U256, denominator: U256) -> Result<U256, MathError> {
+    ensure!(!denominator.is_zero(), MathError::DivByZero);
+    let result = a.full_mul(b) / U512::from(denominator);
+    U256::try_from(result).map_err(|_| MathError::MulDivOverflow)
+}
+
+/// Calculates ceil(a×b÷denominator) with full precision.
+pub fn mul_div_rounding_up(a: U256, b: U256, denominator: U256) -> Result<U256, MathError> {
+    ensure!(!denominator.is_zero(), MathError::DivByZero);
+    let (result, remainder) = a.full_mul(b).div_mod(U512::from(denominator));
+    let result = U256::try_from(result).map_err(|_| MathError::MulDivOverflow)?;
+    if remainder.is_zero() {
+        Ok(result)
+    } else {
+        result
+            .checked_add(1.into())
+            .ok_or(MathError::MulDivOverflow)
+    }
+}
+
+/// Calculates ceil(x÷y).
+pub fn div_rounding_up(x: U256, y: U256) -> Result<U256, MathError> {
+    ensure!(!y.is_zero(), MathError::DivByZero);
+    let (result, remainder) = x.div_mod(y);
+    if remainder.is_zero() {
+        Ok(result)
+    } else {
+        Ok(result + 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mul_div_rejects_zero_denominator() {
+        assert_eq!(mul_div(Q128, 5.into(), 0.into()), Err(MathError::DivByZero));
+        assert_eq!(
+            mul_div_rounding_up(Q128, 5.into(), 0.into()),
+            Err(MathError::DivByZero)
+        );
+        assert_eq!(div_rounding_up(Q128, 0.into()), Err(MathError::DivByZero));
+    }
+
+    #[test]
+    fn mul_div_rejects_overflowing_result() {
+        assert_eq!(
+            mul_div(Q128, Q128, 1.into()),
+            Err(MathError::MulDivOverflow)
+        );
+        assert_eq!(
+            mul_div(U256::MAX, U256::MAX, U256::MAX - 1),
+            Err(MathError::MulDivOverflow)
+        );
+        assert_eq!(
+            mul_div_rounding_up(U256::MAX, U256::MAX, U256::MAX - 1),
+            Err(MathError::MulDivOverflow)
+        );
+    }
+
+    #[test]
+    fn mul_div_handles_phantom_overflow() {
+        // Q128 * 35 * Q128 overflows 256 bits before the division.
+        assert_eq!(
+            mul_div(Q128, U256::from(35) * Q128, U256::from(8) * Q128),
+            Ok(U256::from(4375) * Q128 / 1000)
+        );
+        assert_eq!(
+            mul_div(Q128, U256::from(1000) * Q128, U256::from(3000) * Q128),
+            Ok(Q128 / 3)
+        );
+        assert_eq!(mul_div(U256::MAX, U256::MAX, U256::MAX), Ok(U256::MAX));
+    }
+
+    #[test]
+    fn mul_div_rounding_up_rounds_only_on_remainder() {
+        assert_eq!(
+            mul_div_rounding_up(
+                Q128,
+                U256::from(50) * Q128 / 100,
+                U256::from(150) * Q128 / 100
+            ),
+            Ok(Q128 / 3 + 1)
+        );
+        assert_eq!(
+            mul_div_rounding_up(Q128, U256::from(35) * Q128, U256::from(8) * Q128),
+            Ok(U256::from(4375) * Q128 / 1000)
+        );
+        assert_eq!(
+            mul_div_rounding_up(U256::MAX, U256::MAX, U256::MAX),
+            Ok(U256::MAX)
+        );
+        // a * b / 2 is U256::MAX with a remainder, rounding up would overflow.
+        assert_eq!(
+            mul_div_rounding_up(
+                U256::from_dec_str("535006138814359").unwrap(),
+                U256::from_dec_str(
+                    "432862656469423142931042426214547535783388063929571229938474969"
+                )
+                .unwrap(),
+                2.into()
+            ),
+            Err(MathError::MulDivOverflow)
+        );
+    }
+
+    #[test]
+    fn div_rounding_up_works() {
+        assert_eq!(div_rounding_up(10.into(), 5.into()), Ok(2.into()));
+        assert_eq!(div_rounding_up(11.into(), 5.into()), Ok(3.into()));
+        assert_eq!(div_rounding_up(0.into(), 5.into()), Ok(0.into()));
+    }
+}
@@ -0,0 +1,160 @@
+use crate::helpers::{
+    full_math::mul_div,
+    math::MathError,
+    sqrt_price_math::{
+        get_amount_0_delta,
+        get_amount_1_delta,
+        Q96,
+    },
+};
+use openbrush::traits::Balance;
+use primitive_types::U256;
+
+/// Computes the amount of liquidity received for a given amount of token0 and price range,
+/// i.e. amount0 * (sqrt(upper) * sqrt(lower)) / (sqrt(upper) - sqrt(lower)).
+///
+/// Please refer https://github.com/Uniswap/v3-periphery/blob/main/contracts/libraries/LiquidityAmounts.sol
+pub fn get_liquidity_for_amount_0(
+    sqrt_ratio_a_x96: U256,
+    sqrt_ratio_b_x96: U256,
+    amount_0: Balance,
+) -> Result<u128, MathError> {
+    let (sqrt_ratio_a_x96, sqrt_ratio_b_x96) = sort(sqrt_ratio_a_x96, sqrt_ratio_b_x96);
+    let intermediate = mul_div(sqrt_ratio_a_x96, sqrt_ratio_b_x96, Q96)?;
+    to_u128(mul_div(
+        amount_0.into(),
+        intermediate,
+        sqrt_ratio_b_x96 - sqrt_ratio_a_x96,
+    )?)
+}
+
+/// Computes the amount of liquidity received for a given amount of token1 and price range,
+/// i.e. amount1 / (sqrt(upper) - sqrt(lower)).
+pub fn get_liquidity_for_amount_1(
+    sqrt_ratio_a_x96: U256,
+    sqrt_ratio_b_x96: U256,
+    amount_1: Balance,
+) -> Result<u128, MathError> {
+    let (sqrt_ratio_a_x96, sqrt_ratio_b_x96) = sort(sqrt_ratio_a_x96, sqrt_ratio_b_x96);
+    to_u128(mul_div(
+        amount_1.into(),
+        Q96,
+        sqrt_ratio_b_x96 - sqrt_ratio_a_x96,
+    )?)
+}
+
+/// Computes the maximum amount of liquidity received for the given amounts of token0 and token1,
+/// the current pool price and the prices at the tick boundaries.
+pub fn get_liquidity_for_amounts(
+    sqrt_ratio_x96: U256,
+    sqrt_ratio_a_x96: U256,
+    sqrt_ratio_b_x96: U256,
+    amount_0: Balance,
+    amount_1: Balance,
+) -> Result<u128, MathError> {
+    let (sqrt_ratio_a_x96, sqrt_ratio_b_x96) = sort(sqrt_ratio_a_x96, sqrt_ratio_b_x96);
+
+    if sqrt_ratio_x96 <= sqrt_ratio_a_x96 {
+        get_liquidity_for_amount_0(sqrt_ratio_a_x96, sqrt_ratio_b_x96, amount_0)
+    } else if sqrt_ratio_x96 < sqrt_ratio_b_x96 {
+        let liquidity_0 = get_liquidity_for_amount_0(sqrt_ratio_x96, sqrt_ratio_b_x96, amount_0)?;
+        let liquidity_1 = get_liquidity_for_amount_1(sqrt_ratio_a_x96, sqrt_ratio_x96, amount_1)?;
+        Ok(liquidity_0.min(liquidity_1))
+    } else {
+        get_liquidity_for_amount_1(sqrt_ratio_a_x96, sqrt_ratio_b_x96, amount_1)
+    }
+}
+
+/// Computes the token0 and token1 value for a given amount of liquidity, the current
+/// pool price and the prices at the tick boundaries, rounding down.
+pub fn get_amounts_for_liquidity(
+    sqrt_ratio_x96: U256,
+    sqrt_ratio_a_x96: U256,
+    sqrt_ratio_b_x96: U256,
+    liquidity: u128,
+) -> Result<(Balance, Balance), MathError> {
+    let (sqrt_ratio_a_x96, sqrt_ratio_b_x96) = sort(sqrt_ratio_a_x96, sqrt_ratio_b_x96);
+
+    let (amount_0, amount_1) = if sqrt_ratio_x96 <= sqrt_ratio_a_x96 {
+        (
+            get_amount_0_delta(sqrt_ratio_a_x96, sqrt_ratio_b_x96, liquidity, false)?,
+            U256::zero(),
+        )
+    } else if sqrt_ratio_x96 < sqrt_ratio_b_x96 {
+        (
+            get_amount_0_delta(sqrt_ratio_x96, sqrt_ratio_b_x96, liquidity, false)?,
+            get_amount_1_delta(sqrt_ratio_a_x96, sqrt_ratio_x96, liquidity, false)?,
+        )
+    } else {
+        (
+            U256::zero(),
+            get_amount_1_delta(sqrt_ratio_a_x96, sqrt_ratio_b_x96, liquidity, false)?,
+        )
+    };
+    Ok((to_u128(amount_0)?, to_u128(amount_1)?))
+}
+
+fn sort(a: U256, b: U256) -> (U256, U256) {
+    if a > b {
+        (b, a)
+    } else {
+        (a, b)
+    }
+}
+
+fn to_u128(value: U256) -> Result<u128, MathError> {
+    value.try_into().map_err(|_| MathError::Overflow)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dec(value: &str) -> U256 {
+        U256::from_dec_str(value).unwrap()
+    }
+
+    // encodePriceSqrt(100, 110) and encodePriceSqrt(110, 100)
+    fn range() -> (U256, U256) {
+        (
+            dec("75541088972021052632782079082"),
+            dec("83095197869223157896060286990"),
+        )
+    }
+
+    #[test]
+    fn get_liquidity_for_amounts_in_range() {
+        let (a, b) = range();
+        assert_eq!(get_liquidity_for_amounts(Q96, a, b, 100, 200), Ok(2148));
+        // the order of the boundaries does not matter
+        assert_eq!(get_liquidity_for_amounts(Q96, b, a, 100, 200), Ok(2148));
+    }
+
+    #[test]
+    fn get_liquidity_for_amounts_out_of_range() {
+        let (a, b) = range();
+        // encodePriceSqrt(99, 110), only token0 counts
+        let below = dec("75162434512514379355924140470");
+        assert_eq!(get_liquidity_for_amounts(below, a, b, 100, 200), Ok(1048));
+        // encodePriceSqrt(111, 100), only token1 counts
+        let above = dec("83472048772503575395058907992");
+        assert_eq!(get_liquidity_for_amounts(above, a, b, 100, 200), Ok(2097));
+        // on the boundaries the price counts as out of range
+        assert_eq!(get_liquidity_for_amounts(a, a, b, 100, 200), Ok(1048));
+        assert_eq!(get_liquidity_for_amounts(b, a, b, 100, 200), Ok(2097));
+    }
+
+    #[test]
+    fn get_amounts_for_liquidity_works() {
+        let (a, b) = range();
+        assert_eq!(get_amounts_for_liquidity(Q96, a, b, 2148), Ok((99, 99)));
+        assert_eq!(
+            get_amounts_for_liquidity(dec("75162434512514379355924140470"), a, b, 1048),
+            Ok((99, 0))
+        );
+        assert_eq!(
+            get_amounts_for_liquidity(dec("83472048772503575395058907992"), a, b, 2097),
+            Ok((0, 199))
+        );
+    }
+}
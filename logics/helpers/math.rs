@@ -2,4 +2,31 @@ use primitive_types::U256;
 
 pub fn casted_mul(a: u128, b: u128) -> U256 {
     U256::from(a) * U256::from(b)
-}
\ No newline at end of file
+}
+
+/// Adds a signed liquidity delta to liquidity, failing if it overflows or underflows.
+pub fn add_delta(x: u128, y: i128) -> Result<u128, MathError> {
+    if y < 0 {
+        x.checked_sub(y.unsigned_abs())
+            .ok_or(MathError::LiquiditySub)
+    } else {
+        x.checked_add(y as u128).ok_or(MathError::LiquidityAdd)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum MathError {
+    TickOutOfBounds,
+    SqrtPriceOutOfBounds,
+    MulDivOverflow,
+    DivByZero,
+    Overflow,
+    SqrtPriceOverflow,
+    ZeroSqrtPrice,
+    ZeroLiquidity,
+    InsufficientReserves,
+    InvalidFee,
+    LiquiditySub,
+    LiquidityAdd,
+}
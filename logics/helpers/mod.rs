@@ -1,3 +1,8 @@
+pub mod full_math;
 pub mod helper;
+pub mod liquidity_amounts;
 pub mod math;
-pub mod transfer_helper;
\ No newline at end of file
+pub mod sqrt_price_math;
+pub mod swap_math;
+pub mod tick_math;
+pub mod transfer_helper;
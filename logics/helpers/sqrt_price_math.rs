@@ -0,0 +1,379 @@
+use crate::{
+    ensure,
+    helpers::{
+        full_math::{
+            div_rounding_up,
+            mul_div,
+            mul_div_rounding_up,
+        },
+        math::MathError,
+    },
+};
+use primitive_types::U256;
+
+pub const RESOLUTION: usize = 96;
+pub const Q96: U256 = U256([0, 1 << 32, 0, 0]);
+/// Largest value representable by a Q64.96 sqrt price.
+pub const MAX_U160: U256 = U256([u64::MAX, u64::MAX, u32::MAX as u64, 0]);
+
+/// Gets the next sqrt price given a delta of token0, always rounding up so that the price moves
+/// at least far enough to get the desired output amount when `add` is false, and never too far
+/// in the case of exact input when `add` is true.
+///
+/// Please refer https://github.com/Uniswap/v3-core/blob/main/contracts/libraries/SqrtPriceMath.sol#L28
+pub fn get_next_sqrt_price_from_amount_0_rounding_up(
+    sqrt_price_x96: U256,
+    liquidity: u128,
+    amount: U256,
+    add: bool,
+) -> Result<U256, MathError> {
+    if amount.is_zero() {
+        return Ok(sqrt_price_x96)
+    }
+    let numerator_1 = U256::from(liquidity) << RESOLUTION;
+
+    if add {
+        if let Some(product) = amount.checked_mul(sqrt_price_x96) {
+            if let Some(denominator) = numerator_1.checked_add(product) {
+                return mul_div_rounding_up(numerator_1, sqrt_price_x96, denominator)
+            }
+        }
+        let denominator = (numerator_1 / sqrt_price_x96)
+            .checked_add(amount)
+            .ok_or(MathError::Overflow)?;
+        div_rounding_up(numerator_1, denominator)
+    } else {
+        let product = amount
+            .checked_mul(sqrt_price_x96)
+            .ok_or(MathError::Overflow)?;
+        ensure!(numerator_1 > product, MathError::InsufficientReserves);
+        let next = mul_div_rounding_up(numerator_1, sqrt_price_x96, numerator_1 - product)?;
+        ensure!(next <= MAX_U160, MathError::SqrtPriceOverflow);
+        Ok(next)
+    }
+}
+
+/// Gets the next sqrt price given a delta of token1, always rounding down so that the price moves
+/// at least far enough to get the desired output amount when `add` is false, and never too far
+/// in the case of exact input when `add` is true.
+///
+/// Please refer https://github.com/Uniswap/v3-core/blob/main/contracts/libraries/SqrtPriceMath.sol#L65
+pub fn get_next_sqrt_price_from_amount_1_rounding_down(
+    sqrt_price_x96: U256,
+    liquidity: u128,
+    amount: U256,
+    add: bool,
+) -> Result<U256, MathError> {
+    if add {
+        let quotient = if amount <= MAX_U160 {
+            (amount << RESOLUTION) / liquidity
+        } else {
+            mul_div(amount, Q96, liquidity.into())?
+        };
+        let next = sqrt_price_x96
+            .checked_add(quotient)
+            .ok_or(MathError::SqrtPriceOverflow)?;
+        ensure!(next <= MAX_U160, MathError::SqrtPriceOverflow);
+        Ok(next)
+    } else {
+        let quotient = if amount <= MAX_U160 {
+            div_rounding_up(amount << RESOLUTION, liquidity.into())?
+        } else {
+            mul_div_rounding_up(amount, Q96, liquidity.into())?
+        };
+        ensure!(sqrt_price_x96 > quotient, MathError::InsufficientReserves);
+        Ok(sqrt_price_x96 - quotient)
+    }
+}
+
+/// Gets the next sqrt price given an input amount of token0 or token1.
+pub fn get_next_sqrt_price_from_input(
+    sqrt_price_x96: U256,
+    liquidity: u128,
+    amount_in: U256,
+    zero_for_one: bool,
+) -> Result<U256, MathError> {
+    ensure!(!sqrt_price_x96.is_zero(), MathError::ZeroSqrtPrice);
+    ensure!(liquidity > 0, MathError::ZeroLiquidity);
+
+    // round to make sure that we don't pass the target price
+    if zero_for_one {
+        get_next_sqrt_price_from_amount_0_rounding_up(sqrt_price_x96, liquidity, amount_in, true)
+    } else {
+        get_next_sqrt_price_from_amount_1_rounding_down(sqrt_price_x96, liquidity, amount_in, true)
+    }
+}
+
+/// Gets the next sqrt price given an output amount of token0 or token1.
+pub fn get_next_sqrt_price_from_output(
+    sqrt_price_x96: U256,
+    liquidity: u128,
+    amount_out: U256,
+    zero_for_one: bool,
+) -> Result<U256, MathError> {
+    ensure!(!sqrt_price_x96.is_zero(), MathError::ZeroSqrtPrice);
+    ensure!(liquidity > 0, MathError::ZeroLiquidity);
+
+    // round to make sure that we pass the target price
+    if zero_for_one {
+        get_next_sqrt_price_from_amount_1_rounding_down(
+            sqrt_price_x96,
+            liquidity,
+            amount_out,
+            false,
+        )
+    } else {
+        get_next_sqrt_price_from_amount_0_rounding_up(sqrt_price_x96, liquidity, amount_out, false)
+    }
+}
+
+/// Gets the amount0 delta between two prices, i.e. liquidity / sqrt(lower) - liquidity / sqrt(upper).
+pub fn get_amount_0_delta(
+    sqrt_ratio_a_x96: U256,
+    sqrt_ratio_b_x96: U256,
+    liquidity: u128,
+    round_up: bool,
+) -> Result<U256, MathError> {
+    let (sqrt_ratio_a_x96, sqrt_ratio_b_x96) = sort(sqrt_ratio_a_x96, sqrt_ratio_b_x96);
+    ensure!(!sqrt_ratio_a_x96.is_zero(), MathError::ZeroSqrtPrice);
+
+    let numerator_1 = U256::from(liquidity) << RESOLUTION;
+    let numerator_2 = sqrt_ratio_b_x96 - sqrt_ratio_a_x96;
+
+    if round_up {
+        div_rounding_up(
+            mul_div_rounding_up(numerator_1, numerator_2, sqrt_ratio_b_x96)?,
+            sqrt_ratio_a_x96,
+        )
+    } else {
+        Ok(mul_div(numerator_1, numerator_2, sqrt_ratio_b_x96)? / sqrt_ratio_a_x96)
+    }
+}
+
+/// Gets the amount1 delta between two prices, i.e. liquidity * (sqrt(upper) - sqrt(lower)).
+pub fn get_amount_1_delta(
+    sqrt_ratio_a_x96: U256,
+    sqrt_ratio_b_x96: U256,
+    liquidity: u128,
+    round_up: bool,
+) -> Result<U256, MathError> {
+    let (sqrt_ratio_a_x96, sqrt_ratio_b_x96) = sort(sqrt_ratio_a_x96, sqrt_ratio_b_x96);
+
+    if round_up {
+        mul_div_rounding_up(liquidity.into(), sqrt_ratio_b_x96 - sqrt_ratio_a_x96, Q96)
+    } else {
+        mul_div(liquidity.into(), sqrt_ratio_b_x96 - sqrt_ratio_a_x96, Q96)
+    }
+}
+
+/// Gets the signed token0 delta for a signed liquidity delta.
+///
+/// Amounts owed to the pool (positive liquidity) are rounded up, amounts paid out
+/// (negative liquidity) are rounded down.
+pub fn get_amount_0_delta_signed(
+    sqrt_ratio_a_x96: U256,
+    sqrt_ratio_b_x96: U256,
+    liquidity: i128,
+) -> Result<i128, MathError> {
+    if liquidity < 0 {
+        let amount = get_amount_0_delta(
+            sqrt_ratio_a_x96,
+            sqrt_ratio_b_x96,
+            liquidity.unsigned_abs(),
+            false,
+        )?;
+        Ok(-to_i128(amount)?)
+    } else {
+        let amount =
+            get_amount_0_delta(sqrt_ratio_a_x96, sqrt_ratio_b_x96, liquidity as u128, true)?;
+        to_i128(amount)
+    }
+}
+
+/// Gets the signed token1 delta for a signed liquidity delta.
+///
+/// Amounts owed to the pool (positive liquidity) are rounded up, amounts paid out
+/// (negative liquidity) are rounded down.
+pub fn get_amount_1_delta_signed(
+    sqrt_ratio_a_x96: U256,
+    sqrt_ratio_b_x96: U256,
+    liquidity: i128,
+) -> Result<i128, MathError> {
+    if liquidity < 0 {
+        let amount = get_amount_1_delta(
+            sqrt_ratio_a_x96,
+            sqrt_ratio_b_x96,
+            liquidity.unsigned_abs(),
+            false,
+        )?;
+        Ok(-to_i128(amount)?)
+    } else {
+        let amount =
+            get_amount_1_delta(sqrt_ratio_a_x96, sqrt_ratio_b_x96, liquidity as u128, true)?;
+        to_i128(amount)
+    }
+}
+
+fn sort(a: U256, b: U256) -> (U256, U256) {
+    if a > b {
+        (b, a)
+    } else {
+        (a, b)
+    }
+}
+
+fn to_i128(value: U256) -> Result<i128, MathError> {
+    ensure!(value <= U256::from(i128::MAX), MathError::Overflow);
+    Ok(value.low_u128() as i128)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ONE_ETHER: u128 = 1_000_000_000_000_000_000;
+
+    fn dec(value: &str) -> U256 {
+        U256::from_dec_str(value).unwrap()
+    }
+
+    // encodePriceSqrt(121, 100)
+    fn price_1_21() -> U256 {
+        dec("87150978765690771352898345369")
+    }
+
+    #[test]
+    fn get_next_sqrt_price_from_input_rejects_zero_price_and_liquidity() {
+        assert_eq!(
+            get_next_sqrt_price_from_input(0.into(), 1, (ONE_ETHER / 10).into(), false),
+            Err(MathError::ZeroSqrtPrice)
+        );
+        assert_eq!(
+            get_next_sqrt_price_from_input(1.into(), 0, (ONE_ETHER / 10).into(), true),
+            Err(MathError::ZeroLiquidity)
+        );
+    }
+
+    #[test]
+    fn get_next_sqrt_price_from_input_returns_input_price_for_zero_amount() {
+        assert_eq!(
+            get_next_sqrt_price_from_input(Q96, ONE_ETHER / 10, 0.into(), true),
+            Ok(Q96)
+        );
+        assert_eq!(
+            get_next_sqrt_price_from_input(Q96, ONE_ETHER / 10, 0.into(), false),
+            Ok(Q96)
+        );
+    }
+
+    #[test]
+    fn get_next_sqrt_price_from_input_works() {
+        assert_eq!(
+            get_next_sqrt_price_from_input(Q96, ONE_ETHER, (ONE_ETHER / 10).into(), false),
+            Ok(price_1_21())
+        );
+        assert_eq!(
+            get_next_sqrt_price_from_input(Q96, ONE_ETHER, (ONE_ETHER / 10).into(), true),
+            Ok(dec("72025602285694852357767227579"))
+        );
+        assert_eq!(
+            get_next_sqrt_price_from_input(Q96, 10 * ONE_ETHER, U256::one() << 100, true),
+            Ok(dec("624999999995069620"))
+        );
+        assert_eq!(
+            get_next_sqrt_price_from_input(Q96, 1, U256::MAX / 2, true),
+            Ok(1.into())
+        );
+    }
+
+    #[test]
+    fn get_next_sqrt_price_from_input_rejects_overflowing_price() {
+        assert_eq!(
+            get_next_sqrt_price_from_input(MAX_U160, 1024, 1024.into(), false),
+            Err(MathError::SqrtPriceOverflow)
+        );
+    }
+
+    #[test]
+    fn get_next_sqrt_price_from_output_works() {
+        assert_eq!(
+            get_next_sqrt_price_from_output(Q96, ONE_ETHER, (ONE_ETHER / 10).into(), true),
+            Ok(dec("71305346262837903834189555302"))
+        );
+        assert_eq!(
+            get_next_sqrt_price_from_output(Q96, ONE_ETHER, (ONE_ETHER / 10).into(), false),
+            Ok(dec("88031291682515930659493278152"))
+        );
+        assert_eq!(
+            get_next_sqrt_price_from_output(
+                dec("20282409603651670423947251286016"),
+                1024,
+                262143.into(),
+                true
+            ),
+            Ok(dec("77371252455336267181195264"))
+        );
+    }
+
+    #[test]
+    fn get_next_sqrt_price_from_output_rejects_draining_virtual_reserves() {
+        let price = dec("20282409603651670423947251286016");
+        assert_eq!(
+            get_next_sqrt_price_from_output(price, 1024, 4.into(), false),
+            Err(MathError::InsufficientReserves)
+        );
+        assert_eq!(
+            get_next_sqrt_price_from_output(price, 1024, 262144.into(), true),
+            Err(MathError::InsufficientReserves)
+        );
+    }
+
+    #[test]
+    fn get_amount_0_delta_works() {
+        assert_eq!(get_amount_0_delta(Q96, price_1_21(), 0, true), Ok(0.into()));
+        assert_eq!(get_amount_0_delta(Q96, Q96, ONE_ETHER, true), Ok(0.into()));
+        assert_eq!(
+            get_amount_0_delta(Q96, price_1_21(), ONE_ETHER, true),
+            Ok(90909090909090910u128.into())
+        );
+        assert_eq!(
+            get_amount_0_delta(price_1_21(), Q96, ONE_ETHER, false),
+            Ok(90909090909090909u128.into())
+        );
+    }
+
+    #[test]
+    fn get_amount_1_delta_works() {
+        assert_eq!(get_amount_1_delta(Q96, price_1_21(), 0, true), Ok(0.into()));
+        assert_eq!(get_amount_1_delta(Q96, Q96, ONE_ETHER, true), Ok(0.into()));
+        assert_eq!(
+            get_amount_1_delta(Q96, price_1_21(), ONE_ETHER, true),
+            Ok(100000000000000000u128.into())
+        );
+        assert_eq!(
+            get_amount_1_delta(price_1_21(), Q96, ONE_ETHER, false),
+            Ok(99999999999999999u128.into())
+        );
+    }
+
+    #[test]
+    fn signed_deltas_round_against_the_caller() {
+        let liquidity = ONE_ETHER as i128;
+        assert_eq!(
+            get_amount_0_delta_signed(Q96, price_1_21(), liquidity),
+            Ok(90909090909090910)
+        );
+        assert_eq!(
+            get_amount_0_delta_signed(Q96, price_1_21(), -liquidity),
+            Ok(-90909090909090909)
+        );
+        assert_eq!(
+            get_amount_1_delta_signed(Q96, price_1_21(), liquidity),
+            Ok(100000000000000000)
+        );
+        assert_eq!(
+            get_amount_1_delta_signed(Q96, price_1_21(), -liquidity),
+            Ok(-99999999999999999)
+        );
+    }
+}
@@ -0,0 +1,275 @@
+use crate::{
+    ensure,
+    helpers::{
+        full_math::{
+            mul_div,
+            mul_div_rounding_up,
+        },
+        math::MathError,
+        sqrt_price_math::{
+            get_amount_0_delta,
+            get_amount_1_delta,
+            get_next_sqrt_price_from_input,
+            get_next_sqrt_price_from_output,
+        },
+    },
+};
+use primitive_types::U256;
+
+/// Fees are expressed in hundredths of a bip, i.e. 1e-6.
+pub const FEE_DENOMINATOR: u32 = 1_000_000;
+
+/// Computes the result of swapping some amount in, or amount out, given the parameters of the swap.
+///
+/// `amount_remaining` is positive for an exact input swap and negative for an exact output swap,
+/// `fee_pips` is the fee taken from the input amount in hundredths of a bip.
+/// Returns `(sqrt_ratio_next_x96, amount_in, amount_out, fee_amount)`.
+///
+/// Please refer https://github.com/Uniswap/v3-core/blob/main/contracts/libraries/SwapMath.sol
+pub fn compute_swap_step(
+    sqrt_ratio_current_x96: U256,
+    sqrt_ratio_target_x96: U256,
+    liquidity: u128,
+    amount_remaining: i128,
+    fee_pips: u32,
+) -> Result<(U256, U256, U256, U256), MathError> {
+    ensure!(fee_pips < FEE_DENOMINATOR, MathError::InvalidFee);
+    let zero_for_one = sqrt_ratio_current_x96 >= sqrt_ratio_target_x96;
+    let exact_in = amount_remaining >= 0;
+    let amount_remaining_abs = U256::from(amount_remaining.unsigned_abs());
+    let fee_complement = U256::from(FEE_DENOMINATOR - fee_pips);
+
+    let mut amount_in = U256::zero();
+    let mut amount_out = U256::zero();
+    let sqrt_ratio_next_x96;
+
+    if exact_in {
+        let amount_remaining_less_fee =
+            mul_div(amount_remaining_abs, fee_complement, FEE_DENOMINATOR.into())?;
+        amount_in = if zero_for_one {
+            get_amount_0_delta(
+                sqrt_ratio_target_x96,
+                sqrt_ratio_current_x96,
+                liquidity,
+                true,
+            )?
+        } else {
+            get_amount_1_delta(
+                sqrt_ratio_current_x96,
+                sqrt_ratio_target_x96,
+                liquidity,
+                true,
+            )?
+        };
+        sqrt_ratio_next_x96 = if amount_remaining_less_fee >= amount_in {
+            sqrt_ratio_target_x96
+        } else {
+            get_next_sqrt_price_from_input(
+                sqrt_ratio_current_x96,
+                liquidity,
+                amount_remaining_less_fee,
+                zero_for_one,
+            )?
+        };
+    } else {
+        amount_out = if zero_for_one {
+            get_amount_1_delta(
+                sqrt_ratio_target_x96,
+                sqrt_ratio_current_x96,
+                liquidity,
+                false,
+            )?
+        } else {
+            get_amount_0_delta(
+                sqrt_ratio_current_x96,
+                sqrt_ratio_target_x96,
+                liquidity,
+                false,
+            )?
+        };
+        sqrt_ratio_next_x96 = if amount_remaining_abs >= amount_out {
+            sqrt_ratio_target_x96
+        } else {
+            get_next_sqrt_price_from_output(
+                sqrt_ratio_current_x96,
+                liquidity,
+                amount_remaining_abs,
+                zero_for_one,
+            )?
+        };
+    }
+
+    let max = sqrt_ratio_target_x96 == sqrt_ratio_next_x96;
+
+    // get the input/output amounts
+    if zero_for_one {
+        if !(max && exact_in) {
+            amount_in =
+                get_amount_0_delta(sqrt_ratio_next_x96, sqrt_ratio_current_x96, liquidity, true)?;
+        }
+        if !max || exact_in {
+            amount_out = get_amount_1_delta(
+                sqrt_ratio_next_x96,
+                sqrt_ratio_current_x96,
+                liquidity,
+                false,
+            )?;
+        }
+    } else {
+        if !(max && exact_in) {
+            amount_in =
+                get_amount_1_delta(sqrt_ratio_current_x96, sqrt_ratio_next_x96, liquidity, true)?;
+        }
+        if !max || exact_in {
+            amount_out = get_amount_0_delta(
+                sqrt_ratio_current_x96,
+                sqrt_ratio_next_x96,
+                liquidity,
+                false,
+            )?;
+        }
+    }
+
+    // cap the output amount to not exceed the remaining output amount
+    if !exact_in && amount_out > amount_remaining_abs {
+        amount_out = amount_remaining_abs;
+    }
+
+    let fee_amount = if exact_in && sqrt_ratio_next_x96 != sqrt_ratio_target_x96 {
+        // we didn't reach the target, so take the remainder of the maximum input as fee
+        amount_remaining_abs - amount_in
+    } else {
+        mul_div_rounding_up(amount_in, fee_pips.into(), fee_complement)?
+    };
+
+    Ok((sqrt_ratio_next_x96, amount_in, amount_out, fee_amount))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::sqrt_price_math::Q96;
+
+    const ONE_ETHER: i128 = 1_000_000_000_000_000_000;
+
+    fn dec(value: &str) -> U256 {
+        U256::from_dec_str(value).unwrap()
+    }
+
+    #[test]
+    fn exact_amount_in_capped_at_price_target() {
+        let target = dec("79623317895830914510639640423");
+        let (sqrt_q, amount_in, amount_out, fee_amount) =
+            compute_swap_step(Q96, target, 2 * ONE_ETHER as u128, ONE_ETHER, 600).unwrap();
+        assert_eq!(sqrt_q, target);
+        assert_eq!(amount_in, dec("9975124224178055"));
+        assert_eq!(fee_amount, dec("5988667735148"));
+        assert_eq!(amount_out, dec("9925619580021728"));
+    }
+
+    #[test]
+    fn exact_amount_out_capped_at_price_target() {
+        let target = dec("79623317895830914510639640423");
+        let (sqrt_q, amount_in, amount_out, fee_amount) =
+            compute_swap_step(Q96, target, 2 * ONE_ETHER as u128, -ONE_ETHER, 600).unwrap();
+        assert_eq!(sqrt_q, target);
+        assert_eq!(amount_in, dec("9975124224178055"));
+        assert_eq!(fee_amount, dec("5988667735148"));
+        assert_eq!(amount_out, dec("9925619580021728"));
+    }
+
+    #[test]
+    fn exact_amount_in_fully_spent() {
+        let target = dec("250541448375047931186413801569");
+        let (sqrt_q, amount_in, amount_out, fee_amount) =
+            compute_swap_step(Q96, target, 2 * ONE_ETHER as u128, ONE_ETHER, 600).unwrap();
+        assert!(sqrt_q < target);
+        assert_eq!(amount_in, dec("999400000000000000"));
+        assert_eq!(fee_amount, dec("600000000000000"));
+        assert_eq!(amount_out, dec("666399946655997866"));
+        assert_eq!(amount_in + fee_amount, U256::from(ONE_ETHER));
+    }
+
+    #[test]
+    fn exact_amount_out_fully_received() {
+        let target = dec("792281625142643375935439503360");
+        let (sqrt_q, amount_in, amount_out, fee_amount) =
+            compute_swap_step(Q96, target, 2 * ONE_ETHER as u128, -ONE_ETHER, 600).unwrap();
+        assert!(sqrt_q < target);
+        assert_eq!(amount_in, dec("2000000000000000000"));
+        assert_eq!(fee_amount, dec("1200720432259356"));
+        assert_eq!(amount_out, U256::from(ONE_ETHER));
+    }
+
+    #[test]
+    fn amount_out_is_capped_at_desired_amount_out() {
+        let (sqrt_q, amount_in, amount_out, fee_amount) = compute_swap_step(
+            dec("417332158212080721273783715441582"),
+            dec("1452870262520218020823638996"),
+            159344665391607089467575320103,
+            -1,
+            1,
+        )
+        .unwrap();
+        assert_eq!(amount_in, 1.into());
+        assert_eq!(fee_amount, 1.into());
+        assert_eq!(amount_out, 1.into());
+        assert_eq!(sqrt_q, dec("417332158212080721273783715441581"));
+    }
+
+    #[test]
+    fn target_price_of_one_uses_partial_input_amount() {
+        let (sqrt_q, amount_in, amount_out, fee_amount) =
+            compute_swap_step(2.into(), 1.into(), 1, 3915081100057732413702495386755767, 1)
+                .unwrap();
+        assert_eq!(amount_in, dec("39614081257132168796771975168"));
+        assert_eq!(fee_amount, dec("39614120871253040049813"));
+        assert_eq!(amount_out, 0.into());
+        assert_eq!(sqrt_q, 1.into());
+    }
+
+    #[test]
+    fn entire_input_amount_taken_as_fee() {
+        let (sqrt_q, amount_in, amount_out, fee_amount) = compute_swap_step(
+            2413.into(),
+            dec("79887613182836312"),
+            1985041575832132834610021537970,
+            10,
+            1872,
+        )
+        .unwrap();
+        assert_eq!(amount_in, 0.into());
+        assert_eq!(fee_amount, 10.into());
+        assert_eq!(amount_out, 0.into());
+        assert_eq!(sqrt_q, 2413.into());
+    }
+
+    #[test]
+    fn rejects_fee_of_one_hundred_percent() {
+        assert_eq!(
+            compute_swap_step(Q96, 1.into(), 1, 10, FEE_DENOMINATOR),
+            Err(MathError::InvalidFee)
+        );
+    }
+
+    #[test]
+    fn handles_intermediate_insufficient_liquidity_for_exact_output() {
+        let sqrt_p = dec("20282409603651670423947251286016");
+
+        let target = sqrt_p * 11 / 10;
+        let (sqrt_q, amount_in, amount_out, fee_amount) =
+            compute_swap_step(sqrt_p, target, 1024, -4, 3000).unwrap();
+        assert_eq!(amount_out, 0.into());
+        assert_eq!(sqrt_q, target);
+        assert_eq!(amount_in, 26215.into());
+        assert_eq!(fee_amount, 79.into());
+
+        let target = sqrt_p * 9 / 10;
+        let (sqrt_q, amount_in, amount_out, fee_amount) =
+            compute_swap_step(sqrt_p, target, 1024, -263000, 3000).unwrap();
+        assert_eq!(amount_out, 26214.into());
+        assert_eq!(sqrt_q, target);
+        assert_eq!(amount_in, 1.into());
+        assert_eq!(fee_amount, 1.into());
+    }
+}
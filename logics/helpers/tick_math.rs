@@ -0,0 +1,248 @@
+use crate::{
+    ensure,
+    helpers::math::MathError,
+};
+use primitive_types::U256;
+
+/// The minimum tick that may be passed to `get_sqrt_ratio_at_tick`, computed from log base 1.0001 of 2**-128.
+pub const MIN_TICK: i32 = -887272;
+/// The maximum tick that may be passed to `get_sqrt_ratio_at_tick`, computed from log base 1.0001 of 2**128.
+pub const MAX_TICK: i32 = -MIN_TICK;
+
+/// The minimum value that can be returned from `get_sqrt_ratio_at_tick`, equivalent to `get_sqrt_ratio_at_tick(MIN_TICK)`.
+pub const MIN_SQRT_RATIO: U256 = U256([4295128739, 0, 0, 0]);
+/// The maximum value that can be returned from `get_sqrt_ratio_at_tick`, equivalent to `get_sqrt_ratio_at_tick(MAX_TICK)`.
+pub const MAX_SQRT_RATIO: U256 = U256([0x5d951d5263988d26, 0xefd1fc6a50648849, 0xfffd8963, 0]);
+
+/// `2**128 / sqrt(1.0001)**(2**i)` as Q128.128 for every bit `i` of an absolute tick.
+const TICK_RATIOS: [u128; 20] = [
+    0xfffcb933bd6fad37aa2d162d1a594001,
+    0xfff97272373d413259a46990580e213a,
+    0xfff2e50f5f656932ef12357cf3c7fdcc,
+    0xffe5caca7e10e4e61c3624eaa0941cd0,
+    0xffcb9843d60f6159c9db58835c926644,
+    0xff973b41fa98c081472e6896dfb254c0,
+    0xff2ea16466c96a3843ec78b326b52861,
+    0xfe5dee046a99a2a811c461f1969c3053,
+    0xfcbe86c7900a88aedcffc83b479aa3a4,
+    0xf987a7253ac413176f2b074cf7815e54,
+    0xf3392b0822b70005940c7a398e4b70f3,
+    0xe7159475a2c29b7443b29c7fa6e889d9,
+    0xd097f3bdfd2022b8845ad8f792aa5825,
+    0xa9f746462d870fdf8a65dc1f90e061e5,
+    0x70d869a156d2a1b890bb3df62baf32f7,
+    0x31be135f97d08fd981231505542fcfa6,
+    0x9aa508b5b7a84e1c677de54f3e99bc9,
+    0x5d6af8dedb81196699c329225ee604,
+    0x2216e584f5fa1ea926041bedfe98,
+    0x48a170391f7dc42444e8fa2,
+];
+
+/// Calculates sqrt(1.0001^tick) * 2^96.
+///
+/// Port of Uniswap V3 `TickMath.getSqrtRatioAtTick`, results are bit-exact with the reference implementation.
+/// Please refer https://github.com/Uniswap/v3-core/blob/main/contracts/libraries/TickMath.sol#L23
+pub fn get_sqrt_ratio_at_tick(tick: i32) -> Result<U256, MathError> {
+    let abs_tick = tick.unsigned_abs();
+    ensure!(abs_tick <= MAX_TICK as u32, MathError::TickOutOfBounds);
+
+    let mut ratio = if abs_tick & 0x1 != 0 {
+        U256::from(TICK_RATIOS[0])
+    } else {
+        U256::one() << 128
+    };
+    for (i, tick_ratio) in TICK_RATIOS.iter().enumerate().skip(1) {
+        if abs_tick & (1 << i) != 0 {
+            ratio = (ratio * U256::from(*tick_ratio)) >> 128;
+        }
+    }
+
+    if tick > 0 {
+        ratio = U256::MAX / ratio;
+    }
+
+    // Divide by 1<<32 rounding up to go from a Q128.128 to a Q128.96.
+    let round_up = if (ratio & U256::from(u32::MAX)).is_zero() {
+        U256::zero()
+    } else {
+        U256::one()
+    };
+    Ok((ratio >> 32) + round_up)
+}
+
+/// Calculates the greatest tick value such that `get_sqrt_ratio_at_tick(tick) <= sqrt_price_x96`.
+///
+/// Port of Uniswap V3 `TickMath.getTickAtSqrtRatio`, results are bit-exact with the reference implementation.
+/// Please refer https://github.com/Uniswap/v3-core/blob/main/contracts/libraries/TickMath.sol#L61
+pub fn get_tick_at_sqrt_ratio(sqrt_price_x96: U256) -> Result<i32, MathError> {
+    ensure!(
+        sqrt_price_x96 >= MIN_SQRT_RATIO && sqrt_price_x96 < MAX_SQRT_RATIO,
+        MathError::SqrtPriceOutOfBounds
+    );
+    let ratio = sqrt_price_x96 << 32;
+
+    let msb = ratio.bits() - 1;
+    let mut r = if msb >= 128 {
+        ratio >> (msb - 127)
+    } else {
+        ratio << (127 - msb)
+    };
+
+    // log_2 is a Q64.64, the fractional part is refined by repeated squaring.
+    let mut log_2: i128 = (msb as i128 - 128) << 64;
+    for i in (50..64).rev() {
+        r = (r * r) >> 127;
+        let f = r >> 128;
+        log_2 |= (f.low_u128() as i128) << i;
+        r >>= f.low_u32();
+    }
+
+    // 128.128 number, kept as two's complement as it does not fit an i128.
+    let log_sqrt10001 = to_twos_complement(log_2)
+        .overflowing_mul(U256::from(255738958999603826347141u128))
+        .0;
+
+    let tick_low = to_i32(arithmetic_shr(
+        log_sqrt10001
+            .overflowing_sub(U256::from(3402992956809132418596140100660247210u128))
+            .0,
+        128,
+    ));
+    let tick_high = to_i32(arithmetic_shr(
+        log_sqrt10001
+            .overflowing_add(U256::from(291339464771989622907027621153398088495u128))
+            .0,
+        128,
+    ));
+
+    if tick_low == tick_high || get_sqrt_ratio_at_tick(tick_high)? > sqrt_price_x96 {
+        Ok(tick_low)
+    } else {
+        Ok(tick_high)
+    }
+}
+
+fn to_twos_complement(value: i128) -> U256 {
+    if value < 0 {
+        !U256::from(value.unsigned_abs()) + 1
+    } else {
+        U256::from(value as u128)
+    }
+}
+
+fn arithmetic_shr(value: U256, shift: usize) -> U256 {
+    if value.bit(255) {
+        !(!value >> shift)
+    } else {
+        value >> shift
+    }
+}
+
+/// Truncates a two's complement value that is known to fit in an i32.
+fn to_i32(value: U256) -> i32 {
+    value.low_u64() as i64 as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_sqrt_ratio_at_tick_rejects_out_of_bounds_ticks() {
+        assert_eq!(
+            get_sqrt_ratio_at_tick(MIN_TICK - 1),
+            Err(MathError::TickOutOfBounds)
+        );
+        assert_eq!(
+            get_sqrt_ratio_at_tick(MAX_TICK + 1),
+            Err(MathError::TickOutOfBounds)
+        );
+    }
+
+    #[test]
+    fn get_sqrt_ratio_at_tick_golden_vectors() {
+        let cases: [(i32, &str); 7] = [
+            (MIN_TICK, "4295128739"),
+            (MIN_TICK + 1, "4295343490"),
+            (-1, "79224201403219477170569942574"),
+            (0, "79228162514264337593543950336"),
+            (1, "79232123823359799118286999568"),
+            (
+                MAX_TICK - 1,
+                "1461373636630004318706518188784493106690254656249",
+            ),
+            (
+                MAX_TICK,
+                "1461446703485210103287273052203988822378723970342",
+            ),
+        ];
+        for (tick, expected) in cases {
+            assert_eq!(
+                get_sqrt_ratio_at_tick(tick),
+                Ok(U256::from_dec_str(expected).unwrap()),
+                "tick {}",
+                tick
+            );
+        }
+        assert_eq!(get_sqrt_ratio_at_tick(MIN_TICK), Ok(MIN_SQRT_RATIO));
+        assert_eq!(get_sqrt_ratio_at_tick(MAX_TICK), Ok(MAX_SQRT_RATIO));
+    }
+
+    #[test]
+    fn get_tick_at_sqrt_ratio_rejects_out_of_bounds_prices() {
+        assert_eq!(
+            get_tick_at_sqrt_ratio(MIN_SQRT_RATIO - 1),
+            Err(MathError::SqrtPriceOutOfBounds)
+        );
+        assert_eq!(
+            get_tick_at_sqrt_ratio(MAX_SQRT_RATIO),
+            Err(MathError::SqrtPriceOutOfBounds)
+        );
+    }
+
+    #[test]
+    fn get_tick_at_sqrt_ratio_golden_vectors() {
+        assert_eq!(get_tick_at_sqrt_ratio(MIN_SQRT_RATIO), Ok(MIN_TICK));
+        assert_eq!(
+            get_tick_at_sqrt_ratio(U256::from_dec_str("4295343490").unwrap()),
+            Ok(MIN_TICK + 1)
+        );
+        assert_eq!(get_tick_at_sqrt_ratio(U256::one() << 96), Ok(0));
+        assert_eq!(
+            get_tick_at_sqrt_ratio(
+                U256::from_dec_str("1461373636630004318706518188784493106690254656249").unwrap()
+            ),
+            Ok(MAX_TICK - 1)
+        );
+        assert_eq!(get_tick_at_sqrt_ratio(MAX_SQRT_RATIO - 1), Ok(MAX_TICK - 1));
+    }
+
+    #[test]
+    fn get_tick_at_sqrt_ratio_is_inverse_of_get_sqrt_ratio_at_tick() {
+        let ticks = [
+            MIN_TICK,
+            -887220,
+            -200000,
+            -69082,
+            -60,
+            -10,
+            -1,
+            0,
+            1,
+            10,
+            60,
+            69082,
+            200000,
+            887220,
+            MAX_TICK - 1,
+        ];
+        for tick in ticks {
+            let sqrt_ratio = get_sqrt_ratio_at_tick(tick).unwrap();
+            assert_eq!(get_tick_at_sqrt_ratio(sqrt_ratio), Ok(tick));
+            // Any price strictly below a tick boundary belongs to the previous tick.
+            if tick > MIN_TICK {
+                assert_eq!(get_tick_at_sqrt_ratio(sqrt_ratio - 1), Ok(tick - 1));
+            }
+        }
+    }
+}
@@ -1,5 +1,4 @@
 use ink_env::Hash;
-use ink_prelude::vec::Vec;
 use openbrush::{
     storage::Mapping,
     traits::AccountId,
@@ -10,8 +9,15 @@ pub const STORAGE_KEY: u32 = openbrush::storage_unique_key!(Data);
 #[derive(Default, Debug)]
 #[openbrush::upgradeable_storage(STORAGE_KEY)]
 pub struct Data {
-    pub get_pool: Mapping<(AccountId,AccountId,u8),AccountId>,
-    pub all_pools: Vec<AccountId>,
-    pub owner: AccountId,
-		pub fee_amount_tick_spacing: Mapping<u8, i8>
+    pub get_pool: Mapping<(AccountId, AccountId, u32), AccountId>,
+    /// Every pool created by the factory, by creation index.
+    pub all_pools: Mapping<u64, AccountId>,
+    pub all_pools_length: u64,
+    /// The pools of each token, by `(token, index)`.
+    pub pools_for_token: Mapping<(AccountId, u64), AccountId>,
+    pub pools_for_token_length: Mapping<AccountId, u64>,
+    /// Code hash instantiated for new pools.
+    pub pool_code_hash: Hash,
+    /// Tick spacing of each enabled fee amount, fees are in hundredths of a bip, i.e. 1e-6.
+    pub fee_amount_tick_spacing: Mapping<u32, i32>,
 }
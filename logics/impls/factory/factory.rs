@@ -8,8 +8,9 @@ use ink_env::{
     hash::Blake2x256,
     Hash,
 };
+use ink_prelude::vec::Vec;
 use openbrush::{
-    modifier_definition,
+    contracts::ownable::*,
     modifiers,
     traits::{
         AccountId,
@@ -18,31 +19,60 @@ use openbrush::{
     },
 };
 
+/// Upper bound of the tick spacing of an enabled fee amount.
+pub const MAX_TICK_SPACING: i32 = 16384;
+
 impl<T> Factory for T
 where
     T: Internal,
-    T: Storage<data::Data>,
+    T: Storage<data::Data> + Storage<ownable::Data>,
 {
-    default fn all_pairs(&self, pid: u64) -> Option<AccountId> {
-        self.data::<data::Data>()
-            .all_pairs
-            .get(pid as usize)
-            .cloned()
+    default fn owner(&self) -> AccountId {
+        self.data::<ownable::Data>().owner
+    }
+
+    default fn all_pools(&self, index: u64) -> Option<AccountId> {
+        self.data::<data::Data>().all_pools.get(&index)
+    }
+
+    default fn all_pools_length(&self) -> u64 {
+        self.data::<data::Data>().all_pools_length
     }
 
-    default fn all_pairs_length(&self) -> u64 {
-        self.data::<data::Data>().all_pairs.len() as u64
+    default fn pools_range(&self, start: u64, count: u64) -> Vec<AccountId> {
+        let data = self.data::<data::Data>();
+        let end = start.saturating_add(count).min(data.all_pools_length);
+        (start..end)
+            .filter_map(|index| data.all_pools.get(&index))
+            .collect()
+    }
+
+    default fn pools_for_token(&self, token: AccountId, start: u64, count: u64) -> Vec<AccountId> {
+        let data = self.data::<data::Data>();
+        let end = start
+            .saturating_add(count)
+            .min(data.pools_for_token_length.get(&token).unwrap_or_default());
+        (start..end)
+            .filter_map(|index| data.pools_for_token.get(&(token, index)))
+            .collect()
+    }
+
+    default fn pools_for_token_length(&self, token: AccountId) -> u64 {
+        self.data::<data::Data>()
+            .pools_for_token_length
+            .get(&token)
+            .unwrap_or_default()
     }
 
-    default fn pair_contract_code_hash(&self) -> Hash {
-        self.data::<data::Data>().pair_contract_code_hash
+    default fn pool_code_hash(&self) -> Hash {
+        self.data::<data::Data>().pool_code_hash
     }
 
     default fn create_pool(
         &mut self,
         token_a: AccountId,
         token_b: AccountId,
-				fee: u8,
+        fee: u32,
     ) -> Result<AccountId, FactoryError> {
         ensure!(token_a != token_b, FactoryError::IdenticalAddresses);
         let token_pair = if token_a < token_b {
@@ -54,82 +84,160 @@ where
         ensure!(
             self.data::<data::Data>()
                 .get_pool
-                .get(&token_pair)
+                .get(&(token_pair.0, token_pair.1, fee))
                 .is_none(),
             FactoryError::PairExists
         );
-				let tick_spacing:i8 = fee_amount_tick_spacing[fee];
-				ensure!(tick_spacing != 0, FactoryError::TickSpacingIsZero);
+        // fee amounts that are not enabled have a tick spacing of 0
+        let tick_spacing = self.fee_amount_tick_spacing(fee);
+        ensure!(tick_spacing != 0, FactoryError::InvalidTickSpacing);
 
-        let salt = Self::env().hash_encoded::<Blake2x256, _>(&token_pair);
-        let pool_contract = self._instantiate_pool(salt.as_ref());
+        let salt = Self::env().hash_encoded::<Blake2x256, _>(&(token_pair.0, token_pair.1, fee));
+        let pool_contract = self._instantiate_pool(salt.as_ref())?;
 
-        PoolRef::initialize(&pool_contract, token_pair.0, token_pair.1)?;
+        PoolRef::setup(
+            &pool_contract,
+            token_pair.0,
+            token_pair.1,
+            fee,
+            tick_spacing,
+        )?;
 
+        // populate the mapping in the reverse direction as well
         self.data::<data::Data>()
-            .get_pair
-            .insert(&(token_pair.0, token_pair.1), &pool_contract);
+            .get_pool
+            .insert(&(token_pair.0, token_pair.1, fee), &pool_contract);
         self.data::<data::Data>()
-            .get_pair
-            .insert(&(token_pair.1, token_pair.0), &pool_contract);
-        self.data::<data::Data>().all_pools.push(pool_contract);
-
-        self._emit_create_pool_event(
+            .get_pool
+            .insert(&(token_pair.1, token_pair.0, fee), &pool_contract);
+        push_pool(
+            self.data::<data::Data>(),
             token_pair.0,
             token_pair.1,
-            fee,
-						tick_spacing,
-						&pool_contract,
+            pool_contract,
         );
+
+        self._emit_create_pool_event(token_pair.0, token_pair.1, fee, tick_spacing, pool_contract);
         Ok(pool_contract)
     }
 
-    #[modifiers(only_fee_setter)]
-    default fn set_fee_to(&mut self, fee_to: AccountId) -> Result<(), FactoryError> {
-        self.data::<data::Data>().fee_to = fee_to;
+    default fn set_owner(&mut self, owner: AccountId) -> Result<(), FactoryError> {
+        Ownable::transfer_ownership(self, owner)?;
         Ok(())
     }
 
-    #[modifiers(only_fee_setter)]
-    default fn set_fee_to_setter(&mut self, fee_to_setter: AccountId) -> Result<(), FactoryError> {
-        self.data::<data::Data>().fee_to_setter = fee_to_setter;
+    #[modifiers(only_owner)]
+    default fn enable_fee_amount(
+        &mut self,
+        fee: u32,
+        tick_spacing: i32,
+    ) -> Result<(), FactoryError> {
+        ensure!(
+            tick_spacing > 0 && tick_spacing <= MAX_TICK_SPACING,
+            FactoryError::InvalidTickSpacing
+        );
+        ensure!(
+            self.fee_amount_tick_spacing(fee) == 0,
+            FactoryError::FeeAmountAlreadyEnabled
+        );
+        self.data::<data::Data>()
+            .fee_amount_tick_spacing
+            .insert(&fee, &tick_spacing);
+        self._emit_fee_amount_enabled_event(fee, tick_spacing);
         Ok(())
     }
 
-    default fn fee_to(&self) -> AccountId {
-        self.data::<data::Data>().fee_to
+    #[modifiers(only_owner)]
+    default fn set_pool_code_hash(&mut self, pool_code_hash: Hash) -> Result<(), FactoryError> {
+        self.data::<data::Data>().pool_code_hash = pool_code_hash;
+        self._emit_pool_code_hash_set_event(pool_code_hash);
+        Ok(())
     }
 
-    default fn fee_to_setter(&self) -> AccountId {
-        self.data::<data::Data>().fee_to_setter
+    #[modifiers(only_owner)]
+    default fn migrate_pool(
+        &mut self,
+        pool: AccountId,
+        new_code_hash: Hash,
+    ) -> Result<(), FactoryError> {
+        PoolRef::set_code_hash(&pool, new_code_hash)?;
+        self._emit_pool_migrated_event(pool, new_code_hash);
+        Ok(())
     }
 
-    default fn get_pair(&self, token_a: AccountId, token_b: AccountId) -> Option<AccountId> {
-        self.data::<data::Data>().get_pair.get(&(token_a, token_b))
+    default fn fee_amount_tick_spacing(&self, fee: u32) -> i32 {
+        self.data::<data::Data>()
+            .fee_amount_tick_spacing
+            .get(&fee)
+            .unwrap_or_default()
+    }
+
+    default fn get_pool(
+        &self,
+        token_a: AccountId,
+        token_b: AccountId,
+        fee: u32,
+    ) -> Option<AccountId> {
+        self.data::<data::Data>()
+            .get_pool
+            .get(&(token_a, token_b, fee))
     }
 }
 
 pub trait Internal {
-    fn _emit_create_pair_event(
+    fn _emit_create_pool_event(
         &self,
         _token_0: AccountId,
         _token_1: AccountId,
-        _pair: AccountId,
-        _pair_len: u64,
+        _fee: u32,
+        _tick_spacing: i32,
+        _pool: AccountId,
     );
 
-    fn _instantiate_pair(&mut self, salt_bytes: &[u8]) -> AccountId;
+    fn _emit_fee_amount_enabled_event(&self, _fee: u32, _tick_spacing: i32);
+
+    fn _emit_owner_changed_event(&self, _old_owner: AccountId, _new_owner: AccountId);
+
+    fn _emit_pool_code_hash_set_event(&self, _pool_code_hash: Hash);
+
+    fn _emit_pool_migrated_event(&self, _pool: AccountId, _new_code_hash: Hash);
+
+    fn _instantiate_pool(&mut self, salt_bytes: &[u8]) -> Result<AccountId, FactoryError>;
 }
 
-#[modifier_definition]
-pub fn only_fee_setter<T, F, R, E>(instance: &mut T, body: F) -> Result<R, E>
-where
-    T: Storage<data::Data>,
-    F: FnOnce(&mut T) -> Result<R, E>,
-    E: From<FactoryError>,
-{
-    if instance.data().fee_to_setter != T::env().caller() {
-        return Err(From::from(FactoryError::CallerIsNotFeeSetter))
+/// Appends a newly created pool to the pool list and to the index of both of its tokens.
+pub fn push_pool(data: &mut data::Data, token_0: AccountId, token_1: AccountId, pool: AccountId) {
+    data.all_pools.insert(&data.all_pools_length, &pool);
+    data.all_pools_length += 1;
+    for token in [token_0, token_1] {
+        let length = data.pools_for_token_length.get(&token).unwrap_or_default();
+        data.pools_for_token.insert(&(token, length), &pool);
+        data.pools_for_token_length.insert(&token, &(length + 1));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[ink_lang::test]
+    fn push_pool_indexes_pools_by_token() {
+        let mut data = data::Data::default();
+        let (token_a, token_b, token_c) = (
+            AccountId::from([0x01; 32]),
+            AccountId::from([0x02; 32]),
+            AccountId::from([0x03; 32]),
+        );
+        let (pool_ab, pool_bc) = (AccountId::from([0x11; 32]), AccountId::from([0x12; 32]));
+        push_pool(&mut data, token_a, token_b, pool_ab);
+        push_pool(&mut data, token_b, token_c, pool_bc);
+
+        assert_eq!(data.all_pools_length, 2);
+        assert_eq!(data.all_pools.get(&0), Some(pool_ab));
+        assert_eq!(data.all_pools.get(&1), Some(pool_bc));
+        assert_eq!(data.pools_for_token_length.get(&token_a), Some(1));
+        assert_eq!(data.pools_for_token_length.get(&token_b), Some(2));
+        assert_eq!(data.pools_for_token.get(&(token_b, 1)), Some(pool_bc));
+        assert_eq!(data.pools_for_token.get(&(token_c, 0)), Some(pool_bc));
     }
-    body(instance)
 }
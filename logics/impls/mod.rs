@@ -1,4 +1,5 @@
 pub mod factory;
 pub mod pair;
+pub mod position_manager;
 pub mod router;
 pub mod wnative;
@@ -1,10 +1,26 @@
-use crate::traits::types::WrappedU256;
+pub use crate::traits::pair::Slot0;
+use crate::{
+    impls::pair::{
+        oracle::Observations,
+        position::Positions,
+        tick::Ticks,
+        tick_bitmap::TickBitmap,
+    },
+    traits::types::WrappedU256,
+};
+use ink_storage::traits::{
+    PackedLayout,
+    SpreadAllocate,
+    SpreadLayout,
+};
 use openbrush::traits::{
     AccountId,
     Balance,
-    Timestamp,
 };
 
+#[cfg(feature = "std")]
+use ink_storage::traits::StorageLayout;
+
 pub const STORAGE_KEY: u32 = openbrush::storage_unique_key!(Data);
 
 #[derive(Default, Debug)]
@@ -13,11 +29,42 @@ pub struct Data {
     pub factory: AccountId,
     pub token_0: AccountId,
     pub token_1: AccountId,
-    pub reserve_0: Balance,
-    pub reserve_1: Balance,
-    pub block_timestamp_last: Timestamp,
-    pub price_0_cumulative_last: WrappedU256,
-    pub price_1_cumulative_last: WrappedU256,
-    pub k_last: WrappedU256,
-    pub lock: bool,
+    pub slot0: Slot0,
+    /// The currently in range liquidity available to the pool.
+    pub liquidity: u128,
+    /// The fee growth as a Q128.128 fees of token0/token1 collected per unit of liquidity for the entire life of the pool.
+    pub fee_growth_global_0_x128: WrappedU256,
+    pub fee_growth_global_1_x128: WrappedU256,
+    /// The amounts of token0 and token1 that are owed to the protocol.
+    pub protocol_fees: ProtocolFees,
+    /// The pool's fee in hundredths of a bip, i.e. 1e-6.
+    pub fee: u32,
+    pub tick_spacing: i32,
+    pub max_liquidity_per_tick: u128,
+    pub ticks: Ticks,
+    pub tick_bitmap: TickBitmap,
+    pub positions: Positions,
+    pub observations: Observations,
+    /// Bumped every time the pool's code is migrated to a new code hash.
+    pub version: u32,
+}
+
+/// Accumulated protocol fees in token0/token1 units.
+#[derive(
+    Default,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    scale::Encode,
+    scale::Decode,
+    SpreadLayout,
+    PackedLayout,
+    SpreadAllocate,
+)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+pub struct ProtocolFees {
+    pub token_0: Balance,
+    pub token_1: Balance,
 }
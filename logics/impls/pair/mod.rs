@@ -1,2 +1,6 @@
 pub mod data;
+pub mod oracle;
 pub mod pair;
+pub mod position;
+pub mod tick;
+pub mod tick_bitmap;
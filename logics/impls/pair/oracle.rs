@@ -0,0 +1,479 @@
+use crate::{
+    ensure,
+    helpers::full_math::mul_div,
+    traits::{
+        pair::PairError,
+        types::WrappedU256,
+    },
+};
+use ink_prelude::vec::Vec;
+use ink_storage::traits::{
+    PackedLayout,
+    SpreadAllocate,
+    SpreadLayout,
+};
+use openbrush::storage::Mapping;
+use primitive_types::U256;
+
+#[cfg(feature = "std")]
+use ink_storage::traits::StorageLayout;
+
+/// A price and liquidity observation written at most once per block.
+///
+/// Please refer https://github.com/Uniswap/v3-core/blob/main/contracts/libraries/Oracle.sol
+#[derive(
+    Default,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    scale::Encode,
+    scale::Decode,
+    SpreadLayout,
+    PackedLayout,
+    SpreadAllocate,
+)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+pub struct Observation {
+    /// The block timestamp of the observation, in seconds.
+    pub block_timestamp: u64,
+    /// The tick accumulator, i.e. tick * time elapsed since the pool was first initialized.
+    pub tick_cumulative: i64,
+    /// The seconds per liquidity, i.e. seconds elapsed / max(1, liquidity) since the pool was first initialized.
+    pub seconds_per_liquidity_cumulative_x128: WrappedU256,
+    /// Whether or not the observation is initialized.
+    pub initialized: bool,
+}
+
+/// Ring buffer of observations keyed by their index.
+pub type Observations = Mapping<u16, Observation>;
+
+/// Transforms a previous observation into a new observation, given the passage of time and the current
+/// tick and liquidity values.
+pub fn transform(
+    last: &Observation,
+    block_timestamp: u64,
+    tick: i32,
+    liquidity: u128,
+) -> Observation {
+    let delta = block_timestamp - last.block_timestamp;
+    let seconds_per_liquidity_cumulative_x128: U256 =
+        last.seconds_per_liquidity_cumulative_x128.into();
+    Observation {
+        block_timestamp,
+        tick_cumulative: last
+            .tick_cumulative
+            .wrapping_add((tick as i64).wrapping_mul(delta as i64)),
+        seconds_per_liquidity_cumulative_x128: seconds_per_liquidity_cumulative_x128
+            .overflowing_add((U256::from(delta) << 128) / liquidity.max(1))
+            .0
+            .into(),
+        initialized: true,
+    }
+}
+
+/// Initializes the oracle by writing the first slot.
+///
+/// Returns `(cardinality, cardinality_next)`, the number of populated and allocated elements.
+pub fn initialize(observations: &mut Observations, time: u64) -> (u16, u16) {
+    observations.insert(
+        &0,
+        &Observation {
+            block_timestamp: time,
+            initialized: true,
+            ..Default::default()
+        },
+    );
+    (1, 1)
+}
+
+/// Writes an oracle observation to the ring buffer.
+///
+/// Writable at most once per block. `index` is the location of the most recently written observation.
+/// If the index is at the end of the allowable array length (according to cardinality), and the next
+/// cardinality is greater than the current one, cardinality may be increased. This restriction is created
+/// to preserve ordering. Returns `(index_updated, cardinality_updated)`.
+pub fn write(
+    observations: &mut Observations,
+    index: u16,
+    block_timestamp: u64,
+    tick: i32,
+    liquidity: u128,
+    cardinality: u16,
+    cardinality_next: u16,
+) -> (u16, u16) {
+    let last = observations.get(&index).unwrap_or_default();
+
+    // early return if we've already written an observation this block
+    if last.block_timestamp == block_timestamp {
+        return (index, cardinality)
+    }
+
+    // if the conditions are right, we can bump the cardinality
+    let cardinality_updated = if cardinality_next > cardinality && index == cardinality - 1 {
+        cardinality_next
+    } else {
+        cardinality
+    };
+
+    let index_updated = ((index as u32 + 1) % cardinality_updated as u32) as u16;
+    observations.insert(
+        &index_updated,
+        &transform(&last, block_timestamp, tick, liquidity),
+    );
+    (index_updated, cardinality_updated)
+}
+
+/// Returns the observation as of `time`, transforming the most recently written one if it is older.
+pub fn latest(
+    observations: &Observations,
+    time: u64,
+    tick: i32,
+    index: u16,
+    liquidity: u128,
+) -> Observation {
+    let last = observations.get(&index).unwrap_or_default();
+    if last.block_timestamp != time {
+        transform(&last, time, tick, liquidity)
+    } else {
+        last
+    }
+}
+
+/// Fetches the observations `before` and `at_or_after` a target, i.e. where `[before, at_or_after]` is satisfied.
+///
+/// The answer must be contained in the array, used when the target is located within the stored observation
+/// boundaries: older than the most recent observation and younger, or the same age as, the oldest observation.
+fn binary_search(
+    observations: &Observations,
+    target: u64,
+    index: u16,
+    cardinality: u16,
+) -> (Observation, Observation) {
+    let cardinality = cardinality as u32;
+    // oldest observation
+    let mut l = (index as u32 + 1) % cardinality;
+    // newest observation
+    let mut r = l + cardinality - 1;
+    loop {
+        let i = (l + r) / 2;
+
+        let before = observations
+            .get(&((i % cardinality) as u16))
+            .unwrap_or_default();
+        // we've landed on an uninitialized tick, keep searching higher (more recently)
+        if !before.initialized {
+            l = i + 1;
+            continue
+        }
+
+        let at_or_after = observations
+            .get(&(((i + 1) % cardinality) as u16))
+            .unwrap_or_default();
+
+        let target_at_or_after = before.block_timestamp <= target;
+
+        // check if we've found the answer!
+        if target_at_or_after && target <= at_or_after.block_timestamp {
+            return (before, at_or_after)
+        }
+
+        if !target_at_or_after {
+            r = i - 1;
+        } else {
+            l = i + 1;
+        }
+    }
+}
+
+/// Fetches the observations `before` and `at_or_after` a given target, i.e. where `[before, at_or_after]`
+/// is satisfied.
+///
+/// Assumes there is at least 1 initialized observation. If the target is newer than the most recent
+/// observation, `at_or_after` is the most recent one transformed to the target.
+fn get_surrounding_observations(
+    observations: &Observations,
+    target: u64,
+    tick: i32,
+    index: u16,
+    liquidity: u128,
+    cardinality: u16,
+) -> Result<(Observation, Observation), PairError> {
+    // optimistically set before to the newest observation
+    let before = observations.get(&index).unwrap_or_default();
+
+    // if the target is chronologically at or after the newest observation, we can early return
+    if before.block_timestamp <= target {
+        if before.block_timestamp == target {
+            // if newest observation equals target, we're in the same block, so we can ignore at_or_after
+            return Ok((before, Observation::default()))
+        } else {
+            // otherwise, we need to transform
+            return Ok((before, transform(&before, target, tick, liquidity)))
+        }
+    }
+
+    // now, set before to the oldest observation
+    let mut before = observations
+        .get(&(((index as u32 + 1) % cardinality as u32) as u16))
+        .unwrap_or_default();
+    if !before.initialized {
+        before = observations.get(&0).unwrap_or_default();
+    }
+
+    // ensure that the target is chronologically at or after the oldest observation
+    ensure!(
+        before.block_timestamp <= target,
+        PairError::OracleTargetTooOld
+    );
+
+    // if we've reached this point, we have to binary search
+    Ok(binary_search(observations, target, index, cardinality))
+}
+
+/// Returns the accumulator values as of `seconds_ago` seconds before `time`.
+///
+/// Returns `(tick_cumulative, seconds_per_liquidity_cumulative_x128)`. Fails if the requested point
+/// is older than the oldest stored observation.
+pub fn observe_single(
+    observations: &Observations,
+    time: u64,
+    seconds_ago: u32,
+    tick: i32,
+    index: u16,
+    liquidity: u128,
+    cardinality: u16,
+) -> Result<(i64, U256), PairError> {
+    if seconds_ago == 0 {
+        let last = latest(observations, time, tick, index, liquidity);
+        return Ok((
+            last.tick_cumulative,
+            last.seconds_per_liquidity_cumulative_x128.into(),
+        ))
+    }
+
+    let target = time
+        .checked_sub(seconds_ago as u64)
+        .ok_or(PairError::OracleTargetTooOld)?;
+
+    let (before, at_or_after) =
+        get_surrounding_observations(observations, target, tick, index, liquidity, cardinality)?;
+
+    if target == before.block_timestamp {
+        // we're at the left boundary
+        Ok((
+            before.tick_cumulative,
+            before.seconds_per_liquidity_cumulative_x128.into(),
+        ))
+    } else if target == at_or_after.block_timestamp {
+        // we're at the right boundary
+        Ok((
+            at_or_after.tick_cumulative,
+            at_or_after.seconds_per_liquidity_cumulative_x128.into(),
+        ))
+    } else {
+        // we're in the middle
+        let observation_time_delta = at_or_after.block_timestamp - before.block_timestamp;
+        let target_delta = target - before.block_timestamp;
+        let tick_cumulative = before.tick_cumulative.wrapping_add(
+            (at_or_after
+                .tick_cumulative
+                .wrapping_sub(before.tick_cumulative)
+                / observation_time_delta as i64)
+                .wrapping_mul(target_delta as i64),
+        );
+        let before_x128: U256 = before.seconds_per_liquidity_cumulative_x128.into();
+        let at_or_after_x128: U256 = at_or_after.seconds_per_liquidity_cumulative_x128.into();
+        let seconds_per_liquidity_cumulative_x128 = before_x128.overflowing_add(mul_div(
+            at_or_after_x128.overflowing_sub(before_x128).0,
+            target_delta.into(),
+            observation_time_delta.into(),
+        )?);
+        Ok((tick_cumulative, seconds_per_liquidity_cumulative_x128.0))
+    }
+}
+
+/// Returns the accumulator values as of each time seconds ago from the given time in the array of `seconds_agos`.
+///
+/// Fails if `seconds_agos` contains a value older than the oldest observation.
+pub fn observe(
+    observations: &Observations,
+    time: u64,
+    seconds_agos: &[u32],
+    tick: i32,
+    index: u16,
+    liquidity: u128,
+    cardinality: u16,
+) -> Result<(Vec<i64>, Vec<U256>), PairError> {
+    ensure!(cardinality > 0, PairError::OracleNotInitialized);
+
+    let mut tick_cumulatives = Vec::with_capacity(seconds_agos.len());
+    let mut seconds_per_liquidity_cumulative_x128s = Vec::with_capacity(seconds_agos.len());
+    for seconds_ago in seconds_agos {
+        let (tick_cumulative, seconds_per_liquidity_cumulative_x128) = observe_single(
+            observations,
+            time,
+            *seconds_ago,
+            tick,
+            index,
+            liquidity,
+            cardinality,
+        )?;
+        tick_cumulatives.push(tick_cumulative);
+        seconds_per_liquidity_cumulative_x128s.push(seconds_per_liquidity_cumulative_x128);
+    }
+    Ok((tick_cumulatives, seconds_per_liquidity_cumulative_x128s))
+}
+
+/// Prepares the oracle array to store up to `next` observations.
+///
+/// Returns the next cardinality which will be populated in the oracle array.
+pub fn grow(observations: &mut Observations, current: u16, next: u16) -> u16 {
+    // no-op if the passed next value isn't greater than the current next value
+    if next <= current {
+        return current
+    }
+    // store in each slot to prevent fresh writes in swaps
+    // this data will not be used because the initialized boolean is still false
+    for i in current..next {
+        observations.insert(
+            &i,
+            &Observation {
+                block_timestamp: 1,
+                ..Default::default()
+            },
+        );
+    }
+    next
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transform_accumulates_tick_and_seconds_per_liquidity() {
+        let last = Observation {
+            block_timestamp: 100,
+            tick_cumulative: 10,
+            initialized: true,
+            ..Default::default()
+        };
+        let next = transform(&last, 105, -3, 4);
+        assert_eq!(next.block_timestamp, 105);
+        assert_eq!(next.tick_cumulative, 10 - 15);
+        assert_eq!(
+            U256::from(next.seconds_per_liquidity_cumulative_x128),
+            (U256::from(5) << 128) / 4
+        );
+        assert!(next.initialized);
+
+        // zero liquidity is treated as one
+        let next = transform(&last, 102, 0, 0);
+        assert_eq!(
+            U256::from(next.seconds_per_liquidity_cumulative_x128),
+            U256::from(2) << 128
+        );
+    }
+
+    #[ink_lang::test]
+    fn write_at_most_once_per_block() {
+        let mut observations = Observations::default();
+        let (cardinality, cardinality_next) = initialize(&mut observations, 1);
+        assert_eq!((cardinality, cardinality_next), (1, 1));
+
+        assert_eq!(write(&mut observations, 0, 1, 3, 4, 1, 1), (0, 1));
+        assert_eq!(observations.get(&0).unwrap().tick_cumulative, 0);
+
+        assert_eq!(write(&mut observations, 0, 6, 3, 4, 1, 1), (0, 1));
+        let observation = observations.get(&0).unwrap();
+        assert_eq!(observation.block_timestamp, 6);
+        assert_eq!(observation.tick_cumulative, 15);
+    }
+
+    #[ink_lang::test]
+    fn write_bumps_cardinality_at_end_of_buffer_and_wraps() {
+        let mut observations = Observations::default();
+        initialize(&mut observations, 0);
+        assert_eq!(grow(&mut observations, 1, 3), 3);
+        assert_eq!(observations.get(&2).unwrap().block_timestamp, 1);
+        assert!(!observations.get(&2).unwrap().initialized);
+        // shrinking is a no-op
+        assert_eq!(grow(&mut observations, 3, 2), 3);
+
+        assert_eq!(write(&mut observations, 0, 3, 1, 1, 1, 3), (1, 3));
+        assert_eq!(write(&mut observations, 1, 4, 1, 1, 3, 3), (2, 3));
+        assert_eq!(write(&mut observations, 2, 7, 2, 1, 3, 3), (0, 3));
+
+        let oldest = observations.get(&1).unwrap();
+        assert_eq!((oldest.block_timestamp, oldest.tick_cumulative), (3, 3));
+        let newest = observations.get(&0).unwrap();
+        assert_eq!((newest.block_timestamp, newest.tick_cumulative), (7, 10));
+    }
+
+    #[ink_lang::test]
+    fn observe_interpolates_between_observations() {
+        let mut observations = Observations::default();
+        initialize(&mut observations, 5);
+        grow(&mut observations, 1, 4);
+        // tick 2 over [5, 15], tick -4 over [15, 20], then tick 6
+        let (index, cardinality) = write(&mut observations, 0, 15, 2, 10, 1, 4);
+        let (index, cardinality) = write(&mut observations, index, 20, -4, 10, cardinality, 4);
+        assert_eq!((index, cardinality), (2, 4));
+
+        let (tick_cumulatives, seconds_per_liquidity_cumulative_x128s) = observe(
+            &observations,
+            23,
+            &[0, 3, 5, 13, 18],
+            6,
+            index,
+            10,
+            cardinality,
+        )
+        .unwrap();
+        assert_eq!(tick_cumulatives, [18, 0, 8, 10, 0]);
+        // 18 seconds in total at a liquidity of 10 at the newest point
+        assert_eq!(
+            seconds_per_liquidity_cumulative_x128s[0],
+            (U256::from(18) << 128) / 10
+        );
+        assert_eq!(seconds_per_liquidity_cumulative_x128s[4], U256::zero());
+    }
+
+    #[ink_lang::test]
+    fn observe_searches_a_wrapped_buffer() {
+        let mut observations = Observations::default();
+        initialize(&mut observations, 0);
+        grow(&mut observations, 1, 3);
+        let mut index = 0;
+        let mut cardinality = 1;
+        // at tick 1 the tick cumulative equals the timestamp, the oldest observations get overwritten
+        for time in [10, 20, 30, 40] {
+            (index, cardinality) = write(&mut observations, index, time, 1, 1, cardinality, 3);
+        }
+        assert_eq!((index, cardinality), (1, 3));
+
+        let (tick_cumulatives, _) =
+            observe(&observations, 40, &[0, 5, 15, 20], 1, index, 1, cardinality).unwrap();
+        assert_eq!(tick_cumulatives, [40, 35, 25, 20]);
+
+        assert_eq!(
+            observe(&observations, 40, &[21], 1, index, 1, cardinality),
+            Err(PairError::OracleTargetTooOld)
+        );
+        assert_eq!(
+            observe(&observations, 40, &[41], 1, index, 1, cardinality),
+            Err(PairError::OracleTargetTooOld)
+        );
+    }
+
+    #[ink_lang::test]
+    fn observe_fails_before_initialization() {
+        let observations = Observations::default();
+        assert_eq!(
+            observe(&observations, 1, &[0], 0, 0, 0, 0),
+            Err(PairError::OracleNotInitialized)
+        );
+    }
+}
@@ -1,10 +1,36 @@
 use crate::{
     ensure,
     helpers::{
-        math::casted_mul,
+        full_math::{
+            mul_div,
+            mul_div_rounding_up,
+            Q128,
+        },
+        math::add_delta,
+        sqrt_price_math::{
+            get_amount_0_delta_signed,
+            get_amount_1_delta_signed,
+        },
+        swap_math::{
+            compute_swap_step,
+            FEE_DENOMINATOR,
+        },
+        tick_math::{
+            get_sqrt_ratio_at_tick,
+            get_tick_at_sqrt_ratio,
+            MAX_SQRT_RATIO,
+            MAX_TICK,
+            MIN_SQRT_RATIO,
+            MIN_TICK,
+        },
         transfer_helper::safe_transfer,
     },
     traits::{
+        callback::{
+            FlashCallbackRef,
+            MintCallbackRef,
+            SwapCallbackRef,
+        },
         factory::FactoryRef,
         types::WrappedU256,
     },
@@ -13,338 +39,804 @@ pub use crate::{
     impls::pair::*,
     traits::pair::*,
 };
+use ink_prelude::vec::Vec;
 use openbrush::{
     contracts::{
         ownable::*,
-        psp22::*,
-        reentrancy_guard::*,
         traits::psp22::PSP22Ref,
     },
+    modifier_definition,
     modifiers,
     traits::{
         AccountId,
-        AccountIdExt,
         Balance,
+        Hash,
         Storage,
-        Timestamp,
-        ZERO_ADDRESS,
     },
 };
 use primitive_types::U256;
-use sp_arithmetic::{
-    traits::IntegerSquareRoot,
-    FixedPointNumber,
-    FixedU128,
-};
-
-pub const MINIMUM_LIQUIDITY: u128 = 1000;
 
 pub trait Internal {
-    fn _mint_fee(&mut self, reserve_0: Balance, reserve_1: Balance) -> Result<bool, PairError>;
+    fn _balance_0(&self) -> Balance;
+
+    fn _balance_1(&self) -> Balance;
+
+    /// Block timestamp in seconds, the resolution used by ticks and the oracle.
+    fn _block_timestamp(&self) -> u64;
 
-    fn _update(
+    /// Effects some changes to a position and returns the amounts of token0/token1
+    /// owed to the pool (positive) or to the recipient (negative).
+    fn _modify_position(
         &mut self,
-        balance_0: Balance,
-        balance_1: Balance,
-        reserve_0: Balance,
-        reserve_1: Balance,
+        owner: AccountId,
+        tick_lower: i32,
+        tick_upper: i32,
+        liquidity_delta: i128,
+    ) -> Result<(i128, i128), PairError>;
+
+    /// Gets and updates a position with the given liquidity delta, along with its ticks.
+    fn _update_position(
+        &mut self,
+        owner: AccountId,
+        tick_lower: i32,
+        tick_upper: i32,
+        liquidity_delta: i128,
+        tick: i32,
     ) -> Result<(), PairError>;
 
-    fn _emit_mint_event(&self, _sender: AccountId, _amount_0: Balance, _amount_1: Balance);
-    fn _emit_burn_event(
+    fn _emit_initialize_event(&self, _sqrt_price_x96: WrappedU256, _tick: i32);
+
+    fn _emit_mint_event(
         &self,
         _sender: AccountId,
+        _owner: AccountId,
+        _tick_lower: i32,
+        _tick_upper: i32,
+        _amount: u128,
+        _amount_0: Balance,
+        _amount_1: Balance,
+    );
+    fn _emit_burn_event(
+        &self,
+        _owner: AccountId,
+        _tick_lower: i32,
+        _tick_upper: i32,
+        _amount: u128,
+        _amount_0: Balance,
+        _amount_1: Balance,
+    );
+
+    fn _emit_collect_event(
+        &self,
+        _owner: AccountId,
+        _recipient: AccountId,
+        _tick_lower: i32,
+        _tick_upper: i32,
         _amount_0: Balance,
         _amount_1: Balance,
-        _to: AccountId,
     );
     fn _emit_swap_event(
         &self,
         _sender: AccountId,
-        _amount_0_in: Balance,
-        _amount_1_in: Balance,
-        _amount_0_out: Balance,
-        _amount_1_out: Balance,
-        _to: AccountId,
+        _recipient: AccountId,
+        _amount_0: i128,
+        _amount_1: i128,
+        _sqrt_price_x96: WrappedU256,
+        _liquidity: u128,
+        _tick: i32,
+    );
+    fn _emit_flash_event(
+        &self,
+        _sender: AccountId,
+        _recipient: AccountId,
+        _amount_0: Balance,
+        _amount_1: Balance,
+        _paid_0: Balance,
+        _paid_1: Balance,
     );
-    fn _emit_sync_event(&self, reserve_0: Balance, reserve_1: Balance);
-}
 
-impl<
-        T: Storage<data::Data>
-            + Storage<ownable::Data>
-            + Storage<psp22::Data>
-            + Storage<reentrancy_guard::Data>,
-    > Pair for T
-{
-    default fn get_reserves(&self) -> (Balance, Balance, Timestamp) {
-        (
-            self.data::<data::Data>().reserve_0,
-            self.data::<data::Data>().reserve_1,
-            self.data::<data::Data>().block_timestamp_last,
-        )
-    }
-    default fn price_0_cumulative_last(&self) -> WrappedU256 {
-        self.data::<data::Data>().price_0_cumulative_last
-    }
+    fn _emit_increase_observation_cardinality_next_event(
+        &self,
+        _observation_cardinality_next_old: u16,
+        _observation_cardinality_next_new: u16,
+    );
 
-    default fn price_1_cumulative_last(&self) -> WrappedU256 {
-        self.data::<data::Data>().price_1_cumulative_last
-    }
+    fn _emit_set_fee_protocol_event(
+        &self,
+        _fee_protocol_0_old: u8,
+        _fee_protocol_1_old: u8,
+        _fee_protocol_0_new: u8,
+        _fee_protocol_1_new: u8,
+    );
 
+    fn _emit_collect_protocol_event(
+        &self,
+        _sender: AccountId,
+        _recipient: AccountId,
+        _amount_0: Balance,
+        _amount_1: Balance,
+    );
+}
+
+impl<T: Storage<data::Data> + Storage<ownable::Data>> Pair for T {
     #[modifiers(only_owner)]
-    default fn initialize(
+    default fn setup(
         &mut self,
         token_0: AccountId,
         token_1: AccountId,
+        fee: u32,
+        tick_spacing: i32,
     ) -> Result<(), PairError> {
-        self.data::<data::Data>().token_0 = token_0;
-        self.data::<data::Data>().token_1 = token_1;
+        ensure!(
+            self.data::<data::Data>().tick_spacing == 0,
+            PairError::AlreadySetUp
+        );
+        ensure!(tick_spacing > 0, PairError::InvalidTickSpacing);
+
+        let data = self.data::<data::Data>();
+        data.token_0 = token_0;
+        data.token_1 = token_1;
+        data.fee = fee;
+        data.tick_spacing = tick_spacing;
+        data.max_liquidity_per_tick = tick::tick_spacing_to_max_liquidity_per_tick(tick_spacing);
         Ok(())
     }
 
-    #[modifiers(non_reentrant)]
-    default fn mint(&mut self, to: AccountId) -> Result<Balance, PairError> {
-        let reserves = self.get_reserves();
-        let contract = Self::env().account_id();
-        let balance_0 = PSP22Ref::balance_of(&self.data::<data::Data>().token_0, contract);
-        let balance_1 = PSP22Ref::balance_of(&self.data::<data::Data>().token_1, contract);
-        let amount_0 = balance_0
-            .checked_sub(reserves.0)
-            .ok_or(PairError::SubUnderFlow1)?;
-        let amount_1 = balance_1
-            .checked_sub(reserves.1)
-            .ok_or(PairError::SubUnderFlow2)?;
-
-        let fee_on = self._mint_fee(reserves.0, reserves.1)?;
-        let total_supply = self.data::<psp22::Data>().supply;
-
-        let liquidity;
-        if total_supply == 0 {
-            let liq = amount_0
-                .checked_mul(amount_1)
-                .ok_or(PairError::MulOverFlow1)?;
-            liquidity = liq
-                .integer_sqrt()
-                .checked_sub(MINIMUM_LIQUIDITY)
-                .ok_or(PairError::SubUnderFlow3)?;
-            self._mint(ZERO_ADDRESS.into(), MINIMUM_LIQUIDITY)?;
-        } else {
-            let liquidity_1 = amount_0
-                .checked_mul(total_supply)
-                .ok_or(PairError::MulOverFlow2)?
-                .checked_div(reserves.0)
-                .ok_or(PairError::DivByZero1)?;
-            let liquidity_2 = amount_1
-                .checked_mul(total_supply)
-                .ok_or(PairError::MulOverFlow3)?
-                .checked_div(reserves.1)
-                .ok_or(PairError::DivByZero2)?;
-            liquidity = min(liquidity_1, liquidity_2);
-        }
+    default fn initialize(&mut self, sqrt_price_x96: WrappedU256) -> Result<(), PairError> {
+        ensure!(
+            self.data::<data::Data>().tick_spacing != 0,
+            PairError::NotSetUp
+        );
+        ensure!(
+            U256::from(self.data::<data::Data>().slot0.sqrt_price_x96).is_zero(),
+            PairError::AlreadyInitialized
+        );
 
-        ensure!(liquidity > 0, PairError::InsufficientLiquidityMinted);
+        let tick = get_tick_at_sqrt_ratio(sqrt_price_x96.into())?;
 
-        self._mint(to, liquidity)?;
+        let time = self._block_timestamp();
+        let data = self.data::<data::Data>();
+        let (cardinality, cardinality_next) = oracle::initialize(&mut data.observations, time);
 
-        self._update(balance_0, balance_1, reserves.0, reserves.1)?;
+        data.slot0 = data::Slot0 {
+            sqrt_price_x96,
+            tick,
+            observation_index: 0,
+            observation_cardinality: cardinality,
+            observation_cardinality_next: cardinality_next,
+            fee_protocol: 0,
+            unlocked: true,
+        };
+
+        self._emit_initialize_event(sqrt_price_x96, tick);
+        Ok(())
+    }
 
-        if fee_on {
-            self.data::<data::Data>().k_last = casted_mul(reserves.0, reserves.1).into();
+    #[modifiers(lock)]
+    default fn increase_observation_cardinality_next(
+        &mut self,
+        observation_cardinality_next: u16,
+    ) -> Result<(), PairError> {
+        let data = self.data::<data::Data>();
+        let observation_cardinality_next_old = data.slot0.observation_cardinality_next;
+        let observation_cardinality_next_new = oracle::grow(
+            &mut data.observations,
+            observation_cardinality_next_old,
+            observation_cardinality_next,
+        );
+        data.slot0.observation_cardinality_next = observation_cardinality_next_new;
+        if observation_cardinality_next_old != observation_cardinality_next_new {
+            self._emit_increase_observation_cardinality_next_event(
+                observation_cardinality_next_old,
+                observation_cardinality_next_new,
+            );
         }
+        Ok(())
+    }
 
-        self._emit_mint_event(Self::env().caller(), amount_0, amount_1);
-
-        Ok(liquidity)
-    }
-
-    #[modifiers(non_reentrant)]
-    default fn burn(&mut self, to: AccountId) -> Result<(Balance, Balance), PairError> {
-        let reserves = self.get_reserves();
-        let contract = Self::env().account_id();
-        let token_0 = self.data::<data::Data>().token_0;
-        let token_1 = self.data::<data::Data>().token_1;
-        let mut balance_0 = PSP22Ref::balance_of(&token_0, contract);
-        let mut balance_1 = PSP22Ref::balance_of(&token_1, contract);
-        let liquidity = self._balance_of(&contract);
-
-        let fee_on = self._mint_fee(reserves.0, reserves.1)?;
-        let total_supply = self.data::<psp22::Data>().supply;
-        let amount_0 = liquidity
-            .checked_mul(balance_0)
-            .ok_or(PairError::MulOverFlow5)?
-            .checked_div(total_supply)
-            .ok_or(PairError::DivByZero3)?;
-        let amount_1 = liquidity
-            .checked_mul(balance_1)
-            .ok_or(PairError::MulOverFlow6)?
-            .checked_div(total_supply)
-            .ok_or(PairError::DivByZero4)?;
+    #[modifiers(lock)]
+    default fn mint(
+        &mut self,
+        recipient: AccountId,
+        tick_lower: i32,
+        tick_upper: i32,
+        amount: u128,
+        data: Vec<u8>,
+    ) -> Result<(Balance, Balance), PairError> {
+        ensure!(amount > 0, PairError::InsufficientLiquidityMinted);
+        let liquidity_delta: i128 = amount.try_into().map_err(|_| PairError::Overflow)?;
+        let (amount_0, amount_1) =
+            self._modify_position(recipient, tick_lower, tick_upper, liquidity_delta)?;
+        // liquidity is only added here, so both amounts are owed to the pool
+        let amount_0 = amount_0 as Balance;
+        let amount_1 = amount_1 as Balance;
 
-        ensure!(
-            amount_0 > 0 && amount_1 > 0,
-            PairError::InsufficientLiquidityBurned
+        let balance_0_before = if amount_0 > 0 { self._balance_0() } else { 0 };
+        let balance_1_before = if amount_1 > 0 { self._balance_1() } else { 0 };
+        let caller = Self::env().caller();
+        MintCallbackRef::mint_callback(&caller, amount_0, amount_1, data)?;
+        if amount_0 > 0 {
+            ensure!(
+                balance_0_before
+                    .checked_add(amount_0)
+                    .ok_or(PairError::Overflow)?
+                    <= self._balance_0(),
+                PairError::InsufficientInputAmount
+            );
+        }
+        if amount_1 > 0 {
+            ensure!(
+                balance_1_before
+                    .checked_add(amount_1)
+                    .ok_or(PairError::Overflow)?
+                    <= self._balance_1(),
+                PairError::InsufficientInputAmount
+            );
+        }
+
+        self._emit_mint_event(
+            caller, recipient, tick_lower, tick_upper, amount, amount_0, amount_1,
         );
 
-        self._burn_from(contract, liquidity)?;
+        Ok((amount_0, amount_1))
+    }
+
+    #[modifiers(lock)]
+    default fn collect(
+        &mut self,
+        recipient: AccountId,
+        tick_lower: i32,
+        tick_upper: i32,
+        amount_0_requested: Balance,
+        amount_1_requested: Balance,
+    ) -> Result<(Balance, Balance), PairError> {
+        let owner = Self::env().caller();
+        let key = (owner, tick_lower, tick_upper);
+        // we don't need to check_ticks here, because invalid positions will never have non-zero tokens_owed
+        let mut position = self
+            .data::<data::Data>()
+            .positions
+            .get(&key)
+            .unwrap_or_default();
+
+        let amount_0 = amount_0_requested.min(position.tokens_owed_0);
+        let amount_1 = amount_1_requested.min(position.tokens_owed_1);
+
+        if amount_0 > 0 || amount_1 > 0 {
+            position.tokens_owed_0 -= amount_0;
+            position.tokens_owed_1 -= amount_1;
+            self.data::<data::Data>().positions.insert(&key, &position);
+        }
+        if amount_0 > 0 {
+            safe_transfer(self.data::<data::Data>().token_0, recipient, amount_0)?;
+        }
+        if amount_1 > 0 {
+            safe_transfer(self.data::<data::Data>().token_1, recipient, amount_1)?;
+        }
 
-        safe_transfer(token_0, to, amount_0)?;
-        safe_transfer(token_1, to, amount_1)?;
+        self._emit_collect_event(owner, recipient, tick_lower, tick_upper, amount_0, amount_1);
 
-        balance_0 = PSP22Ref::balance_of(&token_0, contract);
-        balance_1 = PSP22Ref::balance_of(&token_1, contract);
+        Ok((amount_0, amount_1))
+    }
 
-        self._update(balance_0, balance_1, reserves.0, reserves.1)?;
+    #[modifiers(lock)]
+    default fn burn(
+        &mut self,
+        tick_lower: i32,
+        tick_upper: i32,
+        amount: u128,
+    ) -> Result<(Balance, Balance), PairError> {
+        let owner = Self::env().caller();
+        let liquidity_delta: i128 = amount.try_into().map_err(|_| PairError::Overflow)?;
+        let (amount_0, amount_1) =
+            self._modify_position(owner, tick_lower, tick_upper, -liquidity_delta)?;
+        // liquidity is only removed here, so both amounts are owed to the owner
+        let amount_0 = amount_0.unsigned_abs();
+        let amount_1 = amount_1.unsigned_abs();
 
-        if fee_on {
-            self.data::<data::Data>().k_last = casted_mul(reserves.0, reserves.1).into();
+        if amount_0 > 0 || amount_1 > 0 {
+            let key = (owner, tick_lower, tick_upper);
+            let mut position = self
+                .data::<data::Data>()
+                .positions
+                .get(&key)
+                .unwrap_or_default();
+            position.tokens_owed_0 = position.tokens_owed_0.wrapping_add(amount_0);
+            position.tokens_owed_1 = position.tokens_owed_1.wrapping_add(amount_1);
+            self.data::<data::Data>().positions.insert(&key, &position);
         }
 
-        self._emit_burn_event(Self::env().caller(), amount_0, amount_1, to);
+        self._emit_burn_event(owner, tick_lower, tick_upper, amount, amount_0, amount_1);
 
         Ok((amount_0, amount_1))
     }
 
-    #[modifiers(non_reentrant)]
+    #[modifiers(lock)]
     default fn swap(
         &mut self,
-        amount_0_out: Balance,
-        amount_1_out: Balance,
-        to: AccountId,
-    ) -> Result<(), PairError> {
-        ensure!(
-            amount_0_out > 0 || amount_1_out > 0,
-            PairError::InsufficientOutputAmount
-        );
-        let reserves = self.get_reserves();
+        recipient: AccountId,
+        zero_for_one: bool,
+        amount_specified: i128,
+        sqrt_price_limit_x96: WrappedU256,
+        data: Vec<u8>,
+    ) -> Result<(i128, i128), PairError> {
+        ensure!(amount_specified != 0, PairError::ZeroAmountSpecified);
+
+        let sqrt_price_limit_x96: U256 = sqrt_price_limit_x96.into();
+        let sqrt_price_start_x96: U256 = self.data::<data::Data>().slot0.sqrt_price_x96.into();
         ensure!(
-            amount_0_out < reserves.0 && amount_1_out < reserves.1,
-            PairError::InsufficientLiquidity
+            if zero_for_one {
+                sqrt_price_limit_x96 < sqrt_price_start_x96 && sqrt_price_limit_x96 > MIN_SQRT_RATIO
+            } else {
+                sqrt_price_limit_x96 > sqrt_price_start_x96 && sqrt_price_limit_x96 < MAX_SQRT_RATIO
+            },
+            PairError::InvalidSqrtPriceLimit
         );
 
-        let token_0 = self.data::<data::Data>().token_0;
-        let token_1 = self.data::<data::Data>().token_1;
+        let exact_input = amount_specified > 0;
+        let fee = self.data::<data::Data>().fee;
+        let fee_protocol = if zero_for_one {
+            self.data::<data::Data>().slot0.fee_protocol % 16
+        } else {
+            self.data::<data::Data>().slot0.fee_protocol >> 4
+        };
+        let tick_spacing = self.data::<data::Data>().tick_spacing;
+        let time = self._block_timestamp();
+
+        let mut amount_specified_remaining = amount_specified;
+        let mut amount_calculated: i128 = 0;
+        let mut sqrt_price_x96 = sqrt_price_start_x96;
+        let mut tick = self.data::<data::Data>().slot0.tick;
+        let mut liquidity = self.data::<data::Data>().liquidity;
+        let tick_start = tick;
+        let liquidity_start = liquidity;
+        // only computed once, the first time an initialized tick is crossed
+        let mut latest_observation: Option<oracle::Observation> = None;
+        // amount of input token paid as protocol fee
+        let mut protocol_fee = U256::zero();
+        let fee_growth_global_0_x128: U256 =
+            self.data::<data::Data>().fee_growth_global_0_x128.into();
+        let fee_growth_global_1_x128: U256 =
+            self.data::<data::Data>().fee_growth_global_1_x128.into();
+        // fee growth of the input token
+        let mut fee_growth_global_x128 = if zero_for_one {
+            fee_growth_global_0_x128
+        } else {
+            fee_growth_global_1_x128
+        };
+
+        // continue swapping as long as we haven't used the entire input/output and haven't reached the price limit
+        while amount_specified_remaining != 0 && sqrt_price_x96 != sqrt_price_limit_x96 {
+            let sqrt_price_step_start_x96 = sqrt_price_x96;
+
+            // the bitmap only looks within one word, so the next tick may be an uninitialized word boundary
+            let (mut tick_next, initialized) = tick_bitmap::next_initialized_tick_within_one_word(
+                &self.data::<data::Data>().tick_bitmap,
+                tick,
+                tick_spacing,
+                zero_for_one,
+            );
+            // ensure that we do not overshoot the min/max tick, as the tick bitmap is not aware of these bounds
+            tick_next = tick_next.clamp(MIN_TICK, MAX_TICK);
+            let sqrt_price_next_x96 = get_sqrt_ratio_at_tick(tick_next)?;
+
+            let sqrt_price_target_x96 = if (zero_for_one
+                && sqrt_price_next_x96 < sqrt_price_limit_x96)
+                || (!zero_for_one && sqrt_price_next_x96 > sqrt_price_limit_x96)
+            {
+                sqrt_price_limit_x96
+            } else {
+                sqrt_price_next_x96
+            };
+            let (next_sqrt_price_x96, amount_in, amount_out, fee_amount) = compute_swap_step(
+                sqrt_price_x96,
+                sqrt_price_target_x96,
+                liquidity,
+                amount_specified_remaining,
+                fee,
+            )?;
+            sqrt_price_x96 = next_sqrt_price_x96;
+
+            let amount_in = to_i128(amount_in + fee_amount)?;
+            let amount_out = to_i128(amount_out)?;
+            if exact_input {
+                amount_specified_remaining = amount_specified_remaining
+                    .checked_sub(amount_in)
+                    .ok_or(PairError::Overflow)?;
+                amount_calculated = amount_calculated
+                    .checked_sub(amount_out)
+                    .ok_or(PairError::Overflow)?;
+            } else {
+                amount_specified_remaining = amount_specified_remaining
+                    .checked_add(amount_out)
+                    .ok_or(PairError::Overflow)?;
+                amount_calculated = amount_calculated
+                    .checked_add(amount_in)
+                    .ok_or(PairError::Overflow)?;
+            }
+
+            // if the protocol fee is on, calculate how much is owed, decrement fee_amount, and increment protocol_fee
+            let mut fee_amount = fee_amount;
+            if fee_protocol > 0 {
+                let delta = fee_amount / fee_protocol;
+                fee_amount -= delta;
+                protocol_fee += delta;
+            }
+
+            // update global fee tracker
+            if liquidity > 0 {
+                fee_growth_global_x128 = fee_growth_global_x128
+                    .overflowing_add(mul_div(fee_amount, Q128, liquidity.into())?)
+                    .0;
+            }
+
+            // shift tick if we reached the next price
+            if sqrt_price_x96 == sqrt_price_next_x96 {
+                // if the tick is initialized, run the tick transition
+                if initialized {
+                    let (fee_growth_0_x128, fee_growth_1_x128) = if zero_for_one {
+                        (fee_growth_global_x128, fee_growth_global_1_x128)
+                    } else {
+                        (fee_growth_global_0_x128, fee_growth_global_x128)
+                    };
+                    let data = self.data::<data::Data>();
+                    let observation = *latest_observation.get_or_insert_with(|| {
+                        oracle::latest(
+                            &data.observations,
+                            time,
+                            tick_start,
+                            data.slot0.observation_index,
+                            liquidity_start,
+                        )
+                    });
+                    let mut liquidity_net = tick::cross(
+                        &mut data.ticks,
+                        tick_next,
+                        fee_growth_0_x128,
+                        fee_growth_1_x128,
+                        observation.seconds_per_liquidity_cumulative_x128.into(),
+                        observation.tick_cumulative,
+                        time,
+                    );
+                    // if we're moving leftward, we interpret liquidity_net as the opposite sign
+                    if zero_for_one {
+                        liquidity_net = -liquidity_net;
+                    }
+                    liquidity = add_delta(liquidity, liquidity_net)?;
+                }
+                tick = if zero_for_one {
+                    tick_next - 1
+                } else {
+                    tick_next
+                };
+            } else if sqrt_price_x96 != sqrt_price_step_start_x96 {
+                // recompute unless we're on a lower tick boundary (i.e. already transitioned ticks), and haven't moved
+                tick = get_tick_at_sqrt_ratio(sqrt_price_x96)?;
+            }
+        }
 
-        ensure!(to != token_0 && to != token_1, PairError::InvalidTo);
-        if amount_0_out > 0 {
-            safe_transfer(token_0, to, amount_0_out)?;
+        // update tick and write an oracle entry if the tick changed
+        if tick != tick_start {
+            let data = self.data::<data::Data>();
+            (
+                data.slot0.observation_index,
+                data.slot0.observation_cardinality,
+            ) = oracle::write(
+                &mut data.observations,
+                data.slot0.observation_index,
+                time,
+                tick_start,
+                liquidity_start,
+                data.slot0.observation_cardinality,
+                data.slot0.observation_cardinality_next,
+            );
         }
-        if amount_1_out > 0 {
-            safe_transfer(token_1, to, amount_1_out)?;
+        self.data::<data::Data>().slot0.sqrt_price_x96 = sqrt_price_x96.into();
+        self.data::<data::Data>().slot0.tick = tick;
+        self.data::<data::Data>().liquidity = liquidity;
+        // overflow is acceptable, protocol has to withdraw before it hits Balance::MAX fees
+        let protocol_fee = protocol_fee.low_u128();
+        let data = self.data::<data::Data>();
+        if zero_for_one {
+            data.fee_growth_global_0_x128 = fee_growth_global_x128.into();
+            data.protocol_fees.token_0 = data.protocol_fees.token_0.wrapping_add(protocol_fee);
+        } else {
+            data.fee_growth_global_1_x128 = fee_growth_global_x128.into();
+            data.protocol_fees.token_1 = data.protocol_fees.token_1.wrapping_add(protocol_fee);
         }
-        let contract = Self::env().account_id();
-        let balance_0 = PSP22Ref::balance_of(&token_0, contract);
-        let balance_1 = PSP22Ref::balance_of(&token_1, contract);
 
-        let amount_0_in = if balance_0
-            > reserves
-                .0
-                .checked_sub(amount_0_out)
-                .ok_or(PairError::SubUnderFlow4)?
-        {
-            balance_0
-                .checked_sub(
-                    reserves
-                        .0
-                        .checked_sub(amount_0_out)
-                        .ok_or(PairError::SubUnderFlow5)?,
-                )
-                .ok_or(PairError::SubUnderFlow6)?
+        let amount_specified_used = amount_specified - amount_specified_remaining;
+        let (amount_0, amount_1) = if zero_for_one == exact_input {
+            (amount_specified_used, amount_calculated)
         } else {
-            0
+            (amount_calculated, amount_specified_used)
         };
-        let amount_1_in = if balance_1
-            > reserves
-                .1
-                .checked_sub(amount_1_out)
-                .ok_or(PairError::SubUnderFlow7)?
-        {
-            balance_1
-                .checked_sub(
-                    reserves
-                        .1
-                        .checked_sub(amount_1_out)
-                        .ok_or(PairError::SubUnderFlow8)?,
-                )
-                .ok_or(PairError::SubUnderFlow9)?
+
+        // do the transfers and collect payment
+        let caller = Self::env().caller();
+        if zero_for_one {
+            if amount_1 < 0 {
+                safe_transfer(
+                    self.data::<data::Data>().token_1,
+                    recipient,
+                    amount_1.unsigned_abs(),
+                )?;
+            }
+            let balance_0_before = self._balance_0();
+            SwapCallbackRef::swap_callback(&caller, amount_0, amount_1, data)?;
+            ensure!(
+                balance_0_before
+                    .checked_add(amount_0 as Balance)
+                    .ok_or(PairError::Overflow)?
+                    <= self._balance_0(),
+                PairError::InsufficientInputAmount
+            );
         } else {
-            0
-        };
+            if amount_0 < 0 {
+                safe_transfer(
+                    self.data::<data::Data>().token_0,
+                    recipient,
+                    amount_0.unsigned_abs(),
+                )?;
+            }
+            let balance_1_before = self._balance_1();
+            SwapCallbackRef::swap_callback(&caller, amount_0, amount_1, data)?;
+            ensure!(
+                balance_1_before
+                    .checked_add(amount_1 as Balance)
+                    .ok_or(PairError::Overflow)?
+                    <= self._balance_1(),
+                PairError::InsufficientInputAmount
+            );
+        }
 
-        ensure!(
-            amount_0_in > 0 || amount_1_in > 0,
-            PairError::InsufficientInputAmount
+        self._emit_swap_event(
+            caller,
+            recipient,
+            amount_0,
+            amount_1,
+            sqrt_price_x96.into(),
+            liquidity,
+            tick,
         );
+        Ok((amount_0, amount_1))
+    }
+
+    #[modifiers(lock)]
+    default fn flash(
+        &mut self,
+        recipient: AccountId,
+        amount_0: Balance,
+        amount_1: Balance,
+        data: Vec<u8>,
+    ) -> Result<(), PairError> {
+        let liquidity = self.data::<data::Data>().liquidity;
+        ensure!(liquidity > 0, PairError::InsufficientLiquidity);
+
+        let fee: U256 = self.data::<data::Data>().fee.into();
+        let fee_0 = to_balance(mul_div_rounding_up(
+            amount_0.into(),
+            fee,
+            FEE_DENOMINATOR.into(),
+        )?)?;
+        let fee_1 = to_balance(mul_div_rounding_up(
+            amount_1.into(),
+            fee,
+            FEE_DENOMINATOR.into(),
+        )?)?;
+        let balance_0_before = self._balance_0();
+        let balance_1_before = self._balance_1();
 
-        let balance_0_adjusted = balance_0
-            .checked_mul(1000)
-            .ok_or(PairError::MulOverFlow7)?
-            .checked_sub(amount_0_in.checked_mul(3).ok_or(PairError::MulOverFlow8)?)
-            .ok_or(PairError::SubUnderFlow10)?;
-        let balance_1_adjusted = balance_1
-            .checked_mul(1000)
-            .ok_or(PairError::MulOverFlow9)?
-            .checked_sub(amount_1_in.checked_mul(3).ok_or(PairError::MulOverFlow10)?)
-            .ok_or(PairError::SubUnderFlow11)?;
-
-        // Cast to U256 to prevent Overflow
+        if amount_0 > 0 {
+            safe_transfer(self.data::<data::Data>().token_0, recipient, amount_0)?;
+        }
+        if amount_1 > 0 {
+            safe_transfer(self.data::<data::Data>().token_1, recipient, amount_1)?;
+        }
+
+        let caller = Self::env().caller();
+        FlashCallbackRef::flash_callback(&caller, fee_0, fee_1, data)?;
+
+        let balance_0_after = self._balance_0();
+        let balance_1_after = self._balance_1();
         ensure!(
-            casted_mul(balance_0_adjusted, balance_1_adjusted)
-                >= casted_mul(reserves.0, reserves.1)
-                    .checked_mul(1000u128.pow(2).into())
-                    .ok_or(PairError::MulOverFlow14)?,
-            PairError::K
+            balance_0_before
+                .checked_add(fee_0)
+                .ok_or(PairError::Overflow)?
+                <= balance_0_after,
+            PairError::FlashNotRepaid
+        );
+        ensure!(
+            balance_1_before
+                .checked_add(fee_1)
+                .ok_or(PairError::Overflow)?
+                <= balance_1_after,
+            PairError::FlashNotRepaid
         );
 
-        self._update(balance_0, balance_1, reserves.0, reserves.1)?;
+        // sub is safe because we know balance_after is gt balance_before by at least fee
+        let paid_0 = balance_0_after - balance_0_before;
+        let paid_1 = balance_1_after - balance_1_before;
 
-        self._emit_swap_event(
-            Self::env().caller(),
-            amount_0_in,
-            amount_1_in,
-            amount_0_out,
-            amount_1_out,
-            to,
+        let data = self.data::<data::Data>();
+        if paid_0 > 0 {
+            let fee_protocol_0 = data.slot0.fee_protocol % 16;
+            let fees_0 = if fee_protocol_0 == 0 {
+                0
+            } else {
+                paid_0 / fee_protocol_0 as Balance
+            };
+            data.protocol_fees.token_0 = data.protocol_fees.token_0.wrapping_add(fees_0);
+            let fee_growth: U256 = data.fee_growth_global_0_x128.into();
+            data.fee_growth_global_0_x128 = fee_growth
+                .overflowing_add(mul_div((paid_0 - fees_0).into(), Q128, liquidity.into())?)
+                .0
+                .into();
+        }
+        if paid_1 > 0 {
+            let fee_protocol_1 = data.slot0.fee_protocol >> 4;
+            let fees_1 = if fee_protocol_1 == 0 {
+                0
+            } else {
+                paid_1 / fee_protocol_1 as Balance
+            };
+            data.protocol_fees.token_1 = data.protocol_fees.token_1.wrapping_add(fees_1);
+            let fee_growth: U256 = data.fee_growth_global_1_x128.into();
+            data.fee_growth_global_1_x128 = fee_growth
+                .overflowing_add(mul_div((paid_1 - fees_1).into(), Q128, liquidity.into())?)
+                .0
+                .into();
+        }
+
+        self._emit_flash_event(caller, recipient, amount_0, amount_1, paid_0, paid_1);
+        Ok(())
+    }
+
+    #[modifiers(lock, only_factory_owner)]
+    default fn set_fee_protocol(
+        &mut self,
+        fee_protocol_0: u8,
+        fee_protocol_1: u8,
+    ) -> Result<(), PairError> {
+        ensure!(
+            (fee_protocol_0 == 0 || (4..=10).contains(&fee_protocol_0))
+                && (fee_protocol_1 == 0 || (4..=10).contains(&fee_protocol_1)),
+            PairError::InvalidFeeProtocol
+        );
+        let fee_protocol_old = self.data::<data::Data>().slot0.fee_protocol;
+        self.data::<data::Data>().slot0.fee_protocol = fee_protocol_0 + (fee_protocol_1 << 4);
+        self._emit_set_fee_protocol_event(
+            fee_protocol_old % 16,
+            fee_protocol_old >> 4,
+            fee_protocol_0,
+            fee_protocol_1,
         );
         Ok(())
     }
 
-    #[modifiers(non_reentrant)]
-    default fn skim(&mut self, to: AccountId) -> Result<(), PairError> {
-        let contract = Self::env().account_id();
-        let reserve_0 = self.data::<data::Data>().reserve_0;
-        let reserve_1 = self.data::<data::Data>().reserve_1;
-        let token_0 = self.data::<data::Data>().token_0;
-        let token_1 = self.data::<data::Data>().token_1;
-        let balance_0 = PSP22Ref::balance_of(&token_0, contract);
-        let balance_1 = PSP22Ref::balance_of(&token_1, contract);
-        safe_transfer(
-            token_0,
-            to,
-            balance_0
-                .checked_sub(reserve_0)
-                .ok_or(PairError::SubUnderFlow12)?,
-        )?;
-        safe_transfer(
-            token_1,
-            to,
-            balance_1
-                .checked_sub(reserve_1)
-                .ok_or(PairError::SubUnderFlow13)?,
+    #[modifiers(lock, only_factory_owner)]
+    default fn collect_protocol(
+        &mut self,
+        recipient: AccountId,
+        amount_0_requested: Balance,
+        amount_1_requested: Balance,
+    ) -> Result<(Balance, Balance), PairError> {
+        let data = self.data::<data::Data>();
+        let amount_0 = amount_0_requested.min(data.protocol_fees.token_0);
+        let amount_1 = amount_1_requested.min(data.protocol_fees.token_1);
+        data.protocol_fees.token_0 -= amount_0;
+        data.protocol_fees.token_1 -= amount_1;
+
+        if amount_0 > 0 {
+            safe_transfer(self.data::<data::Data>().token_0, recipient, amount_0)?;
+        }
+        if amount_1 > 0 {
+            safe_transfer(self.data::<data::Data>().token_1, recipient, amount_1)?;
+        }
+
+        self._emit_collect_protocol_event(Self::env().caller(), recipient, amount_0, amount_1);
+        Ok((amount_0, amount_1))
+    }
+
+    default fn protocol_fees(&self) -> (Balance, Balance) {
+        let protocol_fees = self.data::<data::Data>().protocol_fees;
+        (protocol_fees.token_0, protocol_fees.token_1)
+    }
+
+    default fn observe(
+        &self,
+        seconds_agos: Vec<u32>,
+    ) -> Result<(Vec<i64>, Vec<WrappedU256>), PairError> {
+        let data = self.data::<data::Data>();
+        let (tick_cumulatives, seconds_per_liquidity_cumulative_x128s) = oracle::observe(
+            &data.observations,
+            self._block_timestamp(),
+            &seconds_agos,
+            data.slot0.tick,
+            data.slot0.observation_index,
+            data.liquidity,
+            data.slot0.observation_cardinality,
         )?;
-        Ok(())
+        Ok((
+            tick_cumulatives,
+            seconds_per_liquidity_cumulative_x128s
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+        ))
+    }
+
+    default fn snapshot_cumulatives_inside(
+        &self,
+        tick_lower: i32,
+        tick_upper: i32,
+    ) -> Result<(i64, WrappedU256, u64), PairError> {
+        check_ticks(tick_lower, tick_upper)?;
+
+        let data = self.data::<data::Data>();
+        let lower = data.ticks.get(&tick_lower).unwrap_or_default();
+        let upper = data.ticks.get(&tick_upper).unwrap_or_default();
+        ensure!(
+            lower.initialized && upper.initialized,
+            PairError::TickNotInitialized
+        );
+        let lower_seconds_per_liquidity_outside_x128: U256 =
+            lower.seconds_per_liquidity_outside_x128.into();
+        let upper_seconds_per_liquidity_outside_x128: U256 =
+            upper.seconds_per_liquidity_outside_x128.into();
+
+        let slot0 = data.slot0;
+        if slot0.tick < tick_lower {
+            Ok((
+                lower
+                    .tick_cumulative_outside
+                    .wrapping_sub(upper.tick_cumulative_outside),
+                lower_seconds_per_liquidity_outside_x128
+                    .overflowing_sub(upper_seconds_per_liquidity_outside_x128)
+                    .0
+                    .into(),
+                lower.seconds_outside.wrapping_sub(upper.seconds_outside),
+            ))
+        } else if slot0.tick < tick_upper {
+            let time = self._block_timestamp();
+            let (tick_cumulative, seconds_per_liquidity_cumulative_x128) = oracle::observe_single(
+                &data.observations,
+                time,
+                0,
+                slot0.tick,
+                slot0.observation_index,
+                data.liquidity,
+                slot0.observation_cardinality,
+            )?;
+            Ok((
+                tick_cumulative
+                    .wrapping_sub(lower.tick_cumulative_outside)
+                    .wrapping_sub(upper.tick_cumulative_outside),
+                seconds_per_liquidity_cumulative_x128
+                    .overflowing_sub(lower_seconds_per_liquidity_outside_x128)
+                    .0
+                    .overflowing_sub(upper_seconds_per_liquidity_outside_x128)
+                    .0
+                    .into(),
+                time.wrapping_sub(lower.seconds_outside)
+                    .wrapping_sub(upper.seconds_outside),
+            ))
+        } else {
+            Ok((
+                upper
+                    .tick_cumulative_outside
+                    .wrapping_sub(lower.tick_cumulative_outside),
+                upper_seconds_per_liquidity_outside_x128
+                    .overflowing_sub(lower_seconds_per_liquidity_outside_x128)
+                    .0
+                    .into(),
+                upper.seconds_outside.wrapping_sub(lower.seconds_outside),
+            ))
+        }
     }
 
-    #[modifiers(non_reentrant)]
-    default fn sync(&mut self) -> Result<(), PairError> {
-        let contract = Self::env().account_id();
-        let reserve_0 = self.data::<data::Data>().reserve_0;
-        let reserve_1 = self.data::<data::Data>().reserve_1;
-        let token_0 = self.data::<data::Data>().token_0;
-        let token_1 = self.data::<data::Data>().token_1;
-        let balance_0 = PSP22Ref::balance_of(&token_0, contract);
-        let balance_1 = PSP22Ref::balance_of(&token_1, contract);
-        self._update(balance_0, balance_1, reserve_0, reserve_1)
+    default fn slot0(&self) -> data::Slot0 {
+        self.data::<data::Data>().slot0
     }
 
     default fn get_token_0(&self) -> AccountId {
@@ -354,168 +846,359 @@ impl<
     default fn get_token_1(&self) -> AccountId {
         self.data::<data::Data>().token_1
     }
+
+    default fn factory(&self) -> AccountId {
+        self.data::<data::Data>().factory
+    }
+
+    default fn fee(&self) -> u32 {
+        self.data::<data::Data>().fee
+    }
+
+    default fn tick_spacing(&self) -> i32 {
+        self.data::<data::Data>().tick_spacing
+    }
+
+    default fn max_liquidity_per_tick(&self) -> u128 {
+        self.data::<data::Data>().max_liquidity_per_tick
+    }
+
+    default fn positions(
+        &self,
+        owner: AccountId,
+        tick_lower: i32,
+        tick_upper: i32,
+    ) -> position::Position {
+        self.data::<data::Data>()
+            .positions
+            .get(&(owner, tick_lower, tick_upper))
+            .unwrap_or_default()
+    }
+
+    #[modifiers(only_owner)]
+    default fn set_code_hash(&mut self, code_hash: Hash) -> Result<(), PairError> {
+        let code_hash: &[u8; 32] = code_hash
+            .as_ref()
+            .try_into()
+            .map_err(|_| PairError::SetCodeHashFailed)?;
+        ink_env::set_code_hash(code_hash).map_err(|_| PairError::SetCodeHashFailed)?;
+        self.data::<data::Data>().version += 1;
+        Ok(())
+    }
+
+    default fn version(&self) -> u32 {
+        self.data::<data::Data>().version
+    }
 }
 
-fn min(x: u128, y: u128) -> u128 {
-    if x < y {
-        return x
+/// Mutually exclusive reentrancy protection into the pool.
+///
+/// Also prevents entrance to a function before the pool is initialized.
+#[modifier_definition]
+pub fn lock<T, F, R, E>(instance: &mut T, body: F) -> Result<R, E>
+where
+    T: Storage<data::Data>,
+    F: FnOnce(&mut T) -> Result<R, E>,
+    E: From<PairError>,
+{
+    if !instance.data().slot0.unlocked {
+        return Err(From::from(PairError::Locked))
     }
-    y
+    instance.data().slot0.unlocked = false;
+    let result = body(instance);
+    instance.data().slot0.unlocked = true;
+    result
 }
 
-#[inline]
-fn update_cumulative(
-    price_0_cumulative_last: WrappedU256,
-    price_1_cumulative_last: WrappedU256,
-    time_elapsed: U256,
-    reserve_0: Balance,
-    reserve_1: Balance,
-) -> (WrappedU256, WrappedU256) {
-    let price_cumulative_last_0: WrappedU256 = U256::from(
-        FixedU128::checked_from_rational(reserve_1, reserve_0)
-            .unwrap_or_default()
-            .into_inner(),
-    )
-    .saturating_mul(time_elapsed)
-    .saturating_add(price_0_cumulative_last.into())
-    .into();
-    let price_cumulative_last_1: WrappedU256 = U256::from(
-        FixedU128::checked_from_rational(reserve_0, reserve_1)
-            .unwrap_or_default()
-            .into_inner(),
-    )
-    .saturating_mul(time_elapsed)
-    .saturating_add(price_1_cumulative_last.into())
-    .into();
-    (price_cumulative_last_0, price_cumulative_last_1)
+/// Throws if the caller is not the owner of the factory that deployed the pool.
+#[modifier_definition]
+pub fn only_factory_owner<T, F, R, E>(instance: &mut T, body: F) -> Result<R, E>
+where
+    T: Storage<data::Data>,
+    F: FnOnce(&mut T) -> Result<R, E>,
+    E: From<PairError>,
+{
+    let factory = instance.data().factory;
+    if FactoryRef::owner(&factory) != T::env().caller() {
+        return Err(From::from(PairError::CallerIsNotFactoryOwner))
+    }
+    body(instance)
 }
 
-impl<T: Storage<data::Data> + Storage<psp22::Data>> Internal for T {
-    default fn _mint_fee(
+/// Common checks for valid tick inputs.
+fn check_ticks(tick_lower: i32, tick_upper: i32) -> Result<(), PairError> {
+    ensure!(
+        tick_lower < tick_upper,
+        PairError::TickLowerNotLessThanUpper
+    );
+    ensure!(tick_lower >= MIN_TICK, PairError::TickLowerTooLow);
+    ensure!(tick_upper <= MAX_TICK, PairError::TickUpperTooHigh);
+    Ok(())
+}
+
+/// Casts an amount produced by the swap math into a signed amount.
+fn to_i128(value: U256) -> Result<i128, PairError> {
+    ensure!(value <= U256::from(i128::MAX), PairError::Overflow);
+    Ok(value.low_u128() as i128)
+}
+
+/// Casts a fee computed in 256 bits back into a token amount.
+fn to_balance(value: U256) -> Result<Balance, PairError> {
+    ensure!(value <= U256::from(Balance::MAX), PairError::Overflow);
+    Ok(value.low_u128())
+}
+
+impl<T: Storage<data::Data>> Internal for T {
+    default fn _balance_0(&self) -> Balance {
+        PSP22Ref::balance_of(&self.data::<data::Data>().token_0, Self::env().account_id())
+    }
+
+    default fn _balance_1(&self) -> Balance {
+        PSP22Ref::balance_of(&self.data::<data::Data>().token_1, Self::env().account_id())
+    }
+
+    default fn _block_timestamp(&self) -> u64 {
+        Self::env().block_timestamp() / 1000
+    }
+
+    default fn _modify_position(
         &mut self,
-        reserve_0: Balance,
-        reserve_1: Balance,
-    ) -> Result<bool, PairError> {
-        let fee_to = FactoryRef::fee_to(&self.data::<data::Data>().factory);
-        let fee_on = !fee_to.is_zero();
-        let k_last: U256 = self.data::<data::Data>().k_last.into();
-        if fee_on {
-            if !k_last.is_zero() {
-                let root_k: Balance = casted_mul(reserve_0, reserve_1)
-                    .integer_sqrt()
-                    .try_into()
-                    .map_err(|_| PairError::CastOverflow1)?;
-                let root_k_last = k_last
-                    .integer_sqrt()
-                    .try_into()
-                    .map_err(|_| PairError::CastOverflow2)?;
-                if root_k > root_k_last {
-                    let total_supply = self.data::<psp22::Data>().supply;
-                    let numerator = total_supply
-                        .checked_mul(
-                            root_k
-                                .checked_sub(root_k_last)
-                                .ok_or(PairError::SubUnderFlow14)?,
-                        )
-                        .ok_or(PairError::MulOverFlow13)?;
-                    let denominator = root_k
-                        .checked_mul(5)
-                        .ok_or(PairError::MulOverFlow13)?
-                        .checked_add(root_k_last)
-                        .ok_or(PairError::AddOverflow1)?;
-                    let liquidity = numerator
-                        .checked_div(denominator)
-                        .ok_or(PairError::DivByZero5)?;
-                    if liquidity > 0 {
-                        self._mint(fee_to, liquidity)?;
-                    }
-                }
+        owner: AccountId,
+        tick_lower: i32,
+        tick_upper: i32,
+        liquidity_delta: i128,
+    ) -> Result<(i128, i128), PairError> {
+        check_ticks(tick_lower, tick_upper)?;
+
+        let sqrt_price_x96: U256 = self.data::<data::Data>().slot0.sqrt_price_x96.into();
+        let tick = self.data::<data::Data>().slot0.tick;
+
+        self._update_position(owner, tick_lower, tick_upper, liquidity_delta, tick)?;
+
+        let mut amount_0 = 0;
+        let mut amount_1 = 0;
+        if liquidity_delta != 0 {
+            let sqrt_ratio_lower_x96 = get_sqrt_ratio_at_tick(tick_lower)?;
+            let sqrt_ratio_upper_x96 = get_sqrt_ratio_at_tick(tick_upper)?;
+            if tick < tick_lower {
+                // current tick is below the passed range, liquidity can only become in range by crossing from left to
+                // right, when we'll need _more_ token0 (it's becoming more valuable) so user must provide it
+                amount_0 = get_amount_0_delta_signed(
+                    sqrt_ratio_lower_x96,
+                    sqrt_ratio_upper_x96,
+                    liquidity_delta,
+                )?;
+            } else if tick < tick_upper {
+                // current tick is inside the passed range
+                amount_0 = get_amount_0_delta_signed(
+                    sqrt_price_x96,
+                    sqrt_ratio_upper_x96,
+                    liquidity_delta,
+                )?;
+                amount_1 = get_amount_1_delta_signed(
+                    sqrt_ratio_lower_x96,
+                    sqrt_price_x96,
+                    liquidity_delta,
+                )?;
+
+                let liquidity_before = self.data::<data::Data>().liquidity;
+
+                // write an oracle entry
+                let time = self._block_timestamp();
+                let data = self.data::<data::Data>();
+                (
+                    data.slot0.observation_index,
+                    data.slot0.observation_cardinality,
+                ) = oracle::write(
+                    &mut data.observations,
+                    data.slot0.observation_index,
+                    time,
+                    tick,
+                    liquidity_before,
+                    data.slot0.observation_cardinality,
+                    data.slot0.observation_cardinality_next,
+                );
+
+                data.liquidity = add_delta(liquidity_before, liquidity_delta)?;
+            } else {
+                // current tick is above the passed range, liquidity can only become in range by crossing from right to
+                // left, when we'll need _more_ token1 (it's becoming more valuable) so user must provide it
+                amount_1 = get_amount_1_delta_signed(
+                    sqrt_ratio_lower_x96,
+                    sqrt_ratio_upper_x96,
+                    liquidity_delta,
+                )?;
             }
-        } else if !k_last.is_zero() {
-            self.data::<data::Data>().k_last = 0.into();
         }
-        Ok(fee_on)
+        Ok((amount_0, amount_1))
     }
 
-    default fn _update(
+    default fn _update_position(
         &mut self,
-        balance_0: Balance,
-        balance_1: Balance,
-        reserve_0: Balance,
-        reserve_1: Balance,
+        owner: AccountId,
+        tick_lower: i32,
+        tick_upper: i32,
+        liquidity_delta: i128,
+        tick: i32,
     ) -> Result<(), PairError> {
-        ensure!(
-            balance_0 <= u128::MAX && balance_1 <= u128::MAX,
-            PairError::Overflow
-        );
-        let now = Self::env().block_timestamp();
-        let last_timestamp = self.data::<data::Data>().block_timestamp_last;
-        if now != last_timestamp {
-            let (price_0_cumulative_last, price_1_cumulative_last) = update_cumulative(
-                self.data::<data::Data>().price_0_cumulative_last,
-                self.data::<data::Data>().price_1_cumulative_last,
-                now.saturating_sub(last_timestamp).into(),
-                reserve_0,
-                reserve_1,
+        let time = self._block_timestamp();
+        let data = self.data::<data::Data>();
+        let fee_growth_global_0_x128: U256 = data.fee_growth_global_0_x128.into();
+        let fee_growth_global_1_x128: U256 = data.fee_growth_global_1_x128.into();
+
+        // if we need to update the ticks, do it
+        let mut flipped_lower = false;
+        let mut flipped_upper = false;
+        if liquidity_delta != 0 {
+            let observation = oracle::latest(
+                &data.observations,
+                time,
+                tick,
+                data.slot0.observation_index,
+                data.liquidity,
             );
-            self.data::<data::Data>().price_0_cumulative_last = price_0_cumulative_last;
-            self.data::<data::Data>().price_1_cumulative_last = price_1_cumulative_last;
+            let seconds_per_liquidity_cumulative_x128: U256 =
+                observation.seconds_per_liquidity_cumulative_x128.into();
+
+            flipped_lower = tick::update(
+                &mut data.ticks,
+                tick_lower,
+                tick,
+                liquidity_delta,
+                fee_growth_global_0_x128,
+                fee_growth_global_1_x128,
+                seconds_per_liquidity_cumulative_x128,
+                observation.tick_cumulative,
+                time,
+                false,
+                data.max_liquidity_per_tick,
+            )?;
+            flipped_upper = tick::update(
+                &mut data.ticks,
+                tick_upper,
+                tick,
+                liquidity_delta,
+                fee_growth_global_0_x128,
+                fee_growth_global_1_x128,
+                seconds_per_liquidity_cumulative_x128,
+                observation.tick_cumulative,
+                time,
+                true,
+                data.max_liquidity_per_tick,
+            )?;
+
+            if flipped_lower {
+                tick_bitmap::flip_tick(&mut data.tick_bitmap, tick_lower, data.tick_spacing)?;
+            }
+            if flipped_upper {
+                tick_bitmap::flip_tick(&mut data.tick_bitmap, tick_upper, data.tick_spacing)?;
+            }
         }
-        self.data::<data::Data>().reserve_0 = balance_0;
-        self.data::<data::Data>().reserve_1 = balance_1;
-        self.data::<data::Data>().block_timestamp_last = now;
 
-        self._emit_sync_event(reserve_0, reserve_1);
+        let (fee_growth_inside_0_x128, fee_growth_inside_1_x128) = tick::get_fee_growth_inside(
+            &data.ticks,
+            tick_lower,
+            tick_upper,
+            tick,
+            fee_growth_global_0_x128,
+            fee_growth_global_1_x128,
+        );
+
+        position::update(
+            &mut data.positions,
+            owner,
+            tick_lower,
+            tick_upper,
+            liquidity_delta,
+            fee_growth_inside_0_x128,
+            fee_growth_inside_1_x128,
+        )?;
+
+        // clear any tick data that is no longer needed
+        if liquidity_delta < 0 {
+            if flipped_lower {
+                tick::clear(&mut data.ticks, tick_lower);
+            }
+            if flipped_upper {
+                tick::clear(&mut data.ticks, tick_upper);
+            }
+        }
         Ok(())
     }
 
-    default fn _emit_mint_event(&self, _sender: AccountId, _amount_0: Balance, _amount_1: Balance) {
+    default fn _emit_initialize_event(&self, _sqrt_price_x96: WrappedU256, _tick: i32) {}
+    default fn _emit_mint_event(
+        &self,
+        _sender: AccountId,
+        _owner: AccountId,
+        _tick_lower: i32,
+        _tick_upper: i32,
+        _amount: u128,
+        _amount_0: Balance,
+        _amount_1: Balance,
+    ) {
     }
     default fn _emit_burn_event(
         &self,
-        _sender: AccountId,
+        _owner: AccountId,
+        _tick_lower: i32,
+        _tick_upper: i32,
+        _amount: u128,
+        _amount_0: Balance,
+        _amount_1: Balance,
+    ) {
+    }
+    default fn _emit_collect_event(
+        &self,
+        _owner: AccountId,
+        _recipient: AccountId,
+        _tick_lower: i32,
+        _tick_upper: i32,
         _amount_0: Balance,
         _amount_1: Balance,
-        _to: AccountId,
     ) {
     }
     default fn _emit_swap_event(
         &self,
         _sender: AccountId,
-        _amount_0_in: Balance,
-        _amount_1_in: Balance,
-        _amount_0_out: Balance,
-        _amount_1_out: Balance,
-        _to: AccountId,
+        _recipient: AccountId,
+        _amount_0: i128,
+        _amount_1: i128,
+        _sqrt_price_x96: WrappedU256,
+        _liquidity: u128,
+        _tick: i32,
     ) {
     }
-    default fn _emit_sync_event(&self, _reserve_0: Balance, _reserve_1: Balance) {}
-}
-
-#[cfg(test)]
-mod tests {
-    use primitive_types::U256;
-    use sp_arithmetic::FixedU128;
-
-    use super::update_cumulative;
-
-    #[test]
-    fn update_cumulative_from_zero_time_elapsed() {
-        let (cumulative0, cumulative1) = update_cumulative(0.into(), 0.into(), 0.into(), 10, 10);
-        assert_eq!(cumulative0, 0.into());
-        assert_eq!(cumulative1, 0.into());
+    default fn _emit_flash_event(
+        &self,
+        _sender: AccountId,
+        _recipient: AccountId,
+        _amount_0: Balance,
+        _amount_1: Balance,
+        _paid_0: Balance,
+        _paid_1: Balance,
+    ) {
     }
-
-    #[test]
-    fn update_cumulative_from_one_time_elapsed() {
-        let (cumulative0, cumulative1) = update_cumulative(0.into(), 0.into(), 1.into(), 10, 10);
-        assert_eq!(
-            FixedU128::from_inner(U256::from(cumulative0).as_u128()),
-            1.into()
-        );
-        assert_eq!(
-            FixedU128::from_inner(U256::from(cumulative1).as_u128()),
-            1.into()
-        );
+    default fn _emit_increase_observation_cardinality_next_event(
+        &self,
+        _observation_cardinality_next_old: u16,
+        _observation_cardinality_next_new: u16,
+    ) {
+    }
+    default fn _emit_set_fee_protocol_event(
+        &self,
+        _fee_protocol_0_old: u8,
+        _fee_protocol_1_old: u8,
+        _fee_protocol_0_new: u8,
+        _fee_protocol_1_new: u8,
+    ) {
+    }
+    default fn _emit_collect_protocol_event(
+        &self,
+        _sender: AccountId,
+        _recipient: AccountId,
+        _amount_0: Balance,
+        _amount_1: Balance,
+    ) {
     }
 }
@@ -0,0 +1,179 @@
+pub use crate::traits::pair::Position;
+use crate::{
+    ensure,
+    helpers::{
+        full_math::{
+            mul_div,
+            Q128,
+        },
+        math::add_delta,
+    },
+    traits::pair::PairError,
+};
+use openbrush::{
+    storage::Mapping,
+    traits::{
+        AccountId,
+        Balance,
+    },
+};
+use primitive_types::U256;
+
+/// Positions keyed by `(owner, tick_lower, tick_upper)`.
+pub type Positions = Mapping<(AccountId, i32, i32), Position>;
+
+/// Credits accumulated fees to a user's position and applies the liquidity delta.
+pub fn update(
+    positions: &mut Positions,
+    owner: AccountId,
+    tick_lower: i32,
+    tick_upper: i32,
+    liquidity_delta: i128,
+    fee_growth_inside_0_x128: U256,
+    fee_growth_inside_1_x128: U256,
+) -> Result<(), PairError> {
+    let key = (owner, tick_lower, tick_upper);
+    let mut position = positions.get(&key).unwrap_or_default();
+
+    let liquidity_next = if liquidity_delta == 0 {
+        // disallow pokes for 0 liquidity positions
+        ensure!(position.liquidity > 0, PairError::NoPositionLiquidity);
+        position.liquidity
+    } else {
+        add_delta(position.liquidity, liquidity_delta)?
+    };
+
+    // calculate accumulated fees, overflow is acceptable as fees have to be collected before hitting u128::MAX
+    let tokens_owed_0 = owed(
+        fee_growth_inside_0_x128,
+        position.fee_growth_inside_0_last_x128.into(),
+        position.liquidity,
+    )?;
+    let tokens_owed_1 = owed(
+        fee_growth_inside_1_x128,
+        position.fee_growth_inside_1_last_x128.into(),
+        position.liquidity,
+    )?;
+
+    position.liquidity = liquidity_next;
+    position.fee_growth_inside_0_last_x128 = fee_growth_inside_0_x128.into();
+    position.fee_growth_inside_1_last_x128 = fee_growth_inside_1_x128.into();
+    position.tokens_owed_0 = position.tokens_owed_0.wrapping_add(tokens_owed_0);
+    position.tokens_owed_1 = position.tokens_owed_1.wrapping_add(tokens_owed_1);
+
+    positions.insert(&key, &position);
+    Ok(())
+}
+
+/// Fees earned by `liquidity` while the fee growth inside its range went from `fee_growth_inside_last_x128`
+/// to `fee_growth_inside_x128`, tolerating a wrapped around fee growth.
+pub fn owed(
+    fee_growth_inside_x128: U256,
+    fee_growth_inside_last_x128: U256,
+    liquidity: u128,
+) -> Result<Balance, PairError> {
+    Ok(mul_div(
+        fee_growth_inside_x128
+            .overflowing_sub(fee_growth_inside_last_x128)
+            .0,
+        liquidity.into(),
+        Q128,
+    )?
+    .low_u128())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        helpers::math::MathError,
+        traits::types::WrappedU256,
+    };
+
+    const OWNER: [u8; 32] = [0x01; 32];
+
+    fn get(positions: &Positions) -> Position {
+        positions.get(&(OWNER.into(), -60, 60)).unwrap_or_default()
+    }
+
+    #[ink_lang::test]
+    fn update_rejects_poke_of_empty_position() {
+        let mut positions = Positions::default();
+        assert_eq!(
+            update(&mut positions, OWNER.into(), -60, 60, 0, 0.into(), 0.into()),
+            Err(PairError::NoPositionLiquidity)
+        );
+        assert_eq!(
+            update(
+                &mut positions,
+                OWNER.into(),
+                -60,
+                60,
+                -1,
+                0.into(),
+                0.into()
+            ),
+            Err(PairError::MathError(MathError::LiquiditySub))
+        );
+    }
+
+    #[ink_lang::test]
+    fn update_credits_fees_accrued_since_last_update() {
+        let mut positions = Positions::default();
+        update(
+            &mut positions,
+            OWNER.into(),
+            -60,
+            60,
+            100,
+            0.into(),
+            0.into(),
+        )
+        .unwrap();
+        assert_eq!(get(&positions).liquidity, 100);
+
+        // 5 token0 and 2 token1 per unit of liquidity
+        update(&mut positions, OWNER.into(), -60, 60, 0, Q128 * 5, Q128 * 2).unwrap();
+        let position = get(&positions);
+        assert_eq!(position.liquidity, 100);
+        assert_eq!(position.tokens_owed_0, 500);
+        assert_eq!(position.tokens_owed_1, 200);
+        assert_eq!(
+            position.fee_growth_inside_0_last_x128,
+            WrappedU256::from(Q128 * 5)
+        );
+
+        // fees are credited on the liquidity held before the update
+        update(
+            &mut positions,
+            OWNER.into(),
+            -60,
+            60,
+            -100,
+            Q128 * 6,
+            Q128 * 2,
+        )
+        .unwrap();
+        let position = get(&positions);
+        assert_eq!(position.liquidity, 0);
+        assert_eq!(position.tokens_owed_0, 600);
+        assert_eq!(position.tokens_owed_1, 200);
+    }
+
+    #[ink_lang::test]
+    fn update_handles_fee_growth_wrapping() {
+        let mut positions = Positions::default();
+        update(
+            &mut positions,
+            OWNER.into(),
+            -60,
+            60,
+            10,
+            U256::MAX - Q128 + 1,
+            0.into(),
+        )
+        .unwrap();
+        update(&mut positions, OWNER.into(), -60, 60, 0, Q128, 0.into()).unwrap();
+        assert_eq!(get(&positions).tokens_owed_0, 20);
+    }
+}
@@ -0,0 +1,470 @@
+use crate::{
+    ensure,
+    helpers::{
+        math::add_delta,
+        tick_math::{
+            MAX_TICK,
+            MIN_TICK,
+        },
+    },
+    traits::{
+        pair::PairError,
+        types::WrappedU256,
+    },
+};
+use ink_storage::traits::{
+    PackedLayout,
+    SpreadAllocate,
+    SpreadLayout,
+};
+use openbrush::storage::Mapping;
+use primitive_types::U256;
+
+#[cfg(feature = "std")]
+use ink_storage::traits::StorageLayout;
+
+/// Info stored for each initialized individual tick.
+///
+/// Please refer https://github.com/Uniswap/v3-core/blob/main/contracts/libraries/Tick.sol
+#[derive(
+    Default,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    scale::Encode,
+    scale::Decode,
+    SpreadLayout,
+    PackedLayout,
+    SpreadAllocate,
+)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+pub struct Tick {
+    /// The total position liquidity that references this tick.
+    pub liquidity_gross: u128,
+    /// Amount of net liquidity added (subtracted) when tick is crossed from left to right (right to left).
+    pub liquidity_net: i128,
+    /// Fee growth per unit of liquidity on the _other_ side of this tick (relative to the current tick),
+    /// only has relative meaning, not absolute — the value depends on when the tick is initialized.
+    pub fee_growth_outside_0_x128: WrappedU256,
+    pub fee_growth_outside_1_x128: WrappedU256,
+    /// The cumulative tick value on the other side of the tick.
+    pub tick_cumulative_outside: i64,
+    /// The seconds per unit of liquidity on the _other_ side of this tick (relative to the current tick).
+    pub seconds_per_liquidity_outside_x128: WrappedU256,
+    /// The seconds spent on the other side of the tick (relative to the current tick).
+    pub seconds_outside: u64,
+    /// True iff the tick is initialized, i.e. the value is exactly equivalent to `liquidity_gross != 0`.
+    pub initialized: bool,
+}
+
+pub type Ticks = Mapping<i32, Tick>;
+
+/// Derives max liquidity per tick from given tick spacing, so that the liquidity of all usable ticks
+/// together can never overflow a u128.
+pub fn tick_spacing_to_max_liquidity_per_tick(tick_spacing: i32) -> u128 {
+    let min_tick = (MIN_TICK / tick_spacing) * tick_spacing;
+    let max_tick = (MAX_TICK / tick_spacing) * tick_spacing;
+    let num_ticks = ((max_tick - min_tick) / tick_spacing) as u128 + 1;
+    u128::MAX / num_ticks
+}
+
+/// Retrieves fee growth data inside the range `[tick_lower, tick_upper)`.
+///
+/// Returns `(fee_growth_inside_0_x128, fee_growth_inside_1_x128)`, computed with wrapping arithmetic.
+pub fn get_fee_growth_inside(
+    ticks: &Ticks,
+    tick_lower: i32,
+    tick_upper: i32,
+    tick_current: i32,
+    fee_growth_global_0_x128: U256,
+    fee_growth_global_1_x128: U256,
+) -> (U256, U256) {
+    let lower = ticks.get(&tick_lower).unwrap_or_default();
+    let upper = ticks.get(&tick_upper).unwrap_or_default();
+    let lower_outside_0: U256 = lower.fee_growth_outside_0_x128.into();
+    let lower_outside_1: U256 = lower.fee_growth_outside_1_x128.into();
+    let upper_outside_0: U256 = upper.fee_growth_outside_0_x128.into();
+    let upper_outside_1: U256 = upper.fee_growth_outside_1_x128.into();
+
+    // calculate fee growth below
+    let (fee_growth_below_0_x128, fee_growth_below_1_x128) = if tick_current >= tick_lower {
+        (lower_outside_0, lower_outside_1)
+    } else {
+        (
+            fee_growth_global_0_x128.overflowing_sub(lower_outside_0).0,
+            fee_growth_global_1_x128.overflowing_sub(lower_outside_1).0,
+        )
+    };
+
+    // calculate fee growth above
+    let (fee_growth_above_0_x128, fee_growth_above_1_x128) = if tick_current < tick_upper {
+        (upper_outside_0, upper_outside_1)
+    } else {
+        (
+            fee_growth_global_0_x128.overflowing_sub(upper_outside_0).0,
+            fee_growth_global_1_x128.overflowing_sub(upper_outside_1).0,
+        )
+    };
+
+    (
+        fee_growth_global_0_x128
+            .overflowing_sub(fee_growth_below_0_x128)
+            .0
+            .overflowing_sub(fee_growth_above_0_x128)
+            .0,
+        fee_growth_global_1_x128
+            .overflowing_sub(fee_growth_below_1_x128)
+            .0
+            .overflowing_sub(fee_growth_above_1_x128)
+            .0,
+    )
+}
+
+/// Updates a tick and returns true if the tick was flipped from initialized to uninitialized, or vice versa.
+///
+/// `upper` is true when updating a position's upper tick, false for the lower tick.
+#[allow(clippy::too_many_arguments)]
+pub fn update(
+    ticks: &mut Ticks,
+    tick: i32,
+    tick_current: i32,
+    liquidity_delta: i128,
+    fee_growth_global_0_x128: U256,
+    fee_growth_global_1_x128: U256,
+    seconds_per_liquidity_cumulative_x128: U256,
+    tick_cumulative: i64,
+    time: u64,
+    upper: bool,
+    max_liquidity: u128,
+) -> Result<bool, PairError> {
+    let mut info = ticks.get(&tick).unwrap_or_default();
+
+    let liquidity_gross_before = info.liquidity_gross;
+    let liquidity_gross_after = add_delta(liquidity_gross_before, liquidity_delta)?;
+
+    ensure!(
+        liquidity_gross_after <= max_liquidity,
+        PairError::MaxLiquidityPerTick
+    );
+
+    let flipped = (liquidity_gross_after == 0) != (liquidity_gross_before == 0);
+
+    if liquidity_gross_before == 0 {
+        // by convention, we assume that all growth before a tick was initialized happened _below_ the tick
+        if tick <= tick_current {
+            info.fee_growth_outside_0_x128 = fee_growth_global_0_x128.into();
+            info.fee_growth_outside_1_x128 = fee_growth_global_1_x128.into();
+            info.seconds_per_liquidity_outside_x128 = seconds_per_liquidity_cumulative_x128.into();
+            info.tick_cumulative_outside = tick_cumulative;
+            info.seconds_outside = time;
+        }
+        info.initialized = true;
+    }
+
+    info.liquidity_gross = liquidity_gross_after;
+
+    // when the lower (upper) tick is crossed left to right (right to left), liquidity must be added (removed)
+    info.liquidity_net = if upper {
+        info.liquidity_net.checked_sub(liquidity_delta)
+    } else {
+        info.liquidity_net.checked_add(liquidity_delta)
+    }
+    .ok_or(PairError::Overflow)?;
+
+    ticks.insert(&tick, &info);
+    Ok(flipped)
+}
+
+/// Clears tick data.
+pub fn clear(ticks: &mut Ticks, tick: i32) {
+    ticks.remove(&tick);
+}
+
+/// Transitions to next tick as needed by price movement and returns the amount of liquidity
+/// added (subtracted) when tick is crossed from left to right (right to left).
+pub fn cross(
+    ticks: &mut Ticks,
+    tick: i32,
+    fee_growth_global_0_x128: U256,
+    fee_growth_global_1_x128: U256,
+    seconds_per_liquidity_cumulative_x128: U256,
+    tick_cumulative: i64,
+    time: u64,
+) -> i128 {
+    let mut info = ticks.get(&tick).unwrap_or_default();
+    info.fee_growth_outside_0_x128 = fee_growth_global_0_x128
+        .overflowing_sub(info.fee_growth_outside_0_x128.into())
+        .0
+        .into();
+    info.fee_growth_outside_1_x128 = fee_growth_global_1_x128
+        .overflowing_sub(info.fee_growth_outside_1_x128.into())
+        .0
+        .into();
+    info.seconds_per_liquidity_outside_x128 = seconds_per_liquidity_cumulative_x128
+        .overflowing_sub(info.seconds_per_liquidity_outside_x128.into())
+        .0
+        .into();
+    info.tick_cumulative_outside = tick_cumulative.wrapping_sub(info.tick_cumulative_outside);
+    info.seconds_outside = time.wrapping_sub(info.seconds_outside);
+    ticks.insert(&tick, &info);
+    info.liquidity_net
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::math::MathError;
+
+    fn set_fee_growth_outside(ticks: &mut Ticks, tick: i32, outside_0: U256, outside_1: U256) {
+        ticks.insert(
+            &tick,
+            &Tick {
+                fee_growth_outside_0_x128: outside_0.into(),
+                fee_growth_outside_1_x128: outside_1.into(),
+                ..Default::default()
+            },
+        );
+    }
+
+    fn update_simple(
+        ticks: &mut Ticks,
+        tick: i32,
+        liquidity_delta: i128,
+        upper: bool,
+        max_liquidity: u128,
+    ) -> Result<bool, PairError> {
+        update(
+            ticks,
+            tick,
+            0,
+            liquidity_delta,
+            0.into(),
+            0.into(),
+            0.into(),
+            0,
+            0,
+            upper,
+            max_liquidity,
+        )
+    }
+
+    #[test]
+    fn tick_spacing_to_max_liquidity_per_tick_works() {
+        assert_eq!(
+            tick_spacing_to_max_liquidity_per_tick(10),
+            1917569901783203986719870431555990
+        );
+        assert_eq!(
+            tick_spacing_to_max_liquidity_per_tick(60),
+            11505743598341114571880798222544994
+        );
+        assert_eq!(
+            tick_spacing_to_max_liquidity_per_tick(200),
+            38350317471085141830651933667504588
+        );
+        assert_eq!(
+            tick_spacing_to_max_liquidity_per_tick(887272),
+            u128::MAX / 3
+        );
+        assert_eq!(
+            tick_spacing_to_max_liquidity_per_tick(2302),
+            441351967472034323558203122479595605
+        );
+    }
+
+    #[ink_lang::test]
+    fn get_fee_growth_inside_for_uninitialized_ticks() {
+        let ticks = Ticks::default();
+        assert_eq!(
+            get_fee_growth_inside(&ticks, -2, 2, 0, 15.into(), 15.into()),
+            (15.into(), 15.into())
+        );
+        assert_eq!(
+            get_fee_growth_inside(&ticks, -2, 2, 4, 15.into(), 15.into()),
+            (0.into(), 0.into())
+        );
+        assert_eq!(
+            get_fee_growth_inside(&ticks, -2, 2, -4, 15.into(), 15.into()),
+            (0.into(), 0.into())
+        );
+    }
+
+    #[ink_lang::test]
+    fn get_fee_growth_inside_subtracts_outside_growth() {
+        let mut ticks = Ticks::default();
+        set_fee_growth_outside(&mut ticks, 2, 2.into(), 3.into());
+        assert_eq!(
+            get_fee_growth_inside(&ticks, -2, 2, 0, 15.into(), 15.into()),
+            (13.into(), 12.into())
+        );
+
+        let mut ticks = Ticks::default();
+        set_fee_growth_outside(&mut ticks, -2, 2.into(), 3.into());
+        assert_eq!(
+            get_fee_growth_inside(&ticks, -2, 2, 0, 15.into(), 15.into()),
+            (13.into(), 12.into())
+        );
+
+        set_fee_growth_outside(&mut ticks, 2, 4.into(), 1.into());
+        assert_eq!(
+            get_fee_growth_inside(&ticks, -2, 2, 0, 15.into(), 15.into()),
+            (9.into(), 11.into())
+        );
+    }
+
+    #[ink_lang::test]
+    fn get_fee_growth_inside_wraps_on_overflow() {
+        let mut ticks = Ticks::default();
+        set_fee_growth_outside(&mut ticks, -2, U256::MAX - 3, U256::MAX - 2);
+        set_fee_growth_outside(&mut ticks, 2, 3.into(), 5.into());
+        assert_eq!(
+            get_fee_growth_inside(&ticks, -2, 2, 0, 15.into(), 15.into()),
+            (16.into(), 13.into())
+        );
+    }
+
+    #[ink_lang::test]
+    fn update_flips_only_between_zero_and_nonzero() {
+        let mut ticks = Ticks::default();
+        assert_eq!(update_simple(&mut ticks, 0, 1, false, 3), Ok(true));
+        assert_eq!(update_simple(&mut ticks, 0, 1, false, 3), Ok(false));
+        assert_eq!(update_simple(&mut ticks, 0, -1, false, 3), Ok(false));
+        assert_eq!(update_simple(&mut ticks, 0, -1, false, 3), Ok(true));
+        assert_eq!(
+            update_simple(&mut ticks, 0, -1, false, 3),
+            Err(PairError::MathError(MathError::LiquiditySub))
+        );
+    }
+
+    #[ink_lang::test]
+    fn update_enforces_max_liquidity_per_tick() {
+        let mut ticks = Ticks::default();
+        assert_eq!(update_simple(&mut ticks, 0, 2, false, 3), Ok(true));
+        assert_eq!(update_simple(&mut ticks, 0, 1, true, 3), Ok(false));
+        assert_eq!(
+            update_simple(&mut ticks, 0, 1, false, 3),
+            Err(PairError::MaxLiquidityPerTick)
+        );
+    }
+
+    #[ink_lang::test]
+    fn update_nets_liquidity_based_on_upper_flag() {
+        let mut ticks = Ticks::default();
+        update_simple(&mut ticks, 0, 2, false, 10).unwrap();
+        update_simple(&mut ticks, 0, 1, true, 10).unwrap();
+        update_simple(&mut ticks, 0, 3, true, 10).unwrap();
+        update_simple(&mut ticks, 0, 1, false, 10).unwrap();
+        let info = ticks.get(&0).unwrap();
+        assert_eq!(info.liquidity_gross, 2 + 1 + 3 + 1);
+        assert_eq!(info.liquidity_net, 2 - 1 - 3 + 1);
+    }
+
+    #[ink_lang::test]
+    fn update_assumes_growth_happens_below_initialized_ticks() {
+        let mut ticks = Ticks::default();
+        update(
+            &mut ticks,
+            1,
+            1,
+            1,
+            1.into(),
+            2.into(),
+            3.into(),
+            4,
+            5,
+            false,
+            u128::MAX,
+        )
+        .unwrap();
+        // already initialized ticks keep their growth fields
+        update(
+            &mut ticks,
+            1,
+            1,
+            1,
+            6.into(),
+            7.into(),
+            8.into(),
+            9,
+            10,
+            false,
+            u128::MAX,
+        )
+        .unwrap();
+        let info = ticks.get(&1).unwrap();
+        assert_eq!(info.fee_growth_outside_0_x128, 1.into());
+        assert_eq!(info.fee_growth_outside_1_x128, 2.into());
+        assert_eq!(info.seconds_per_liquidity_outside_x128, 3.into());
+        assert_eq!(info.tick_cumulative_outside, 4);
+        assert_eq!(info.seconds_outside, 5);
+
+        // ticks above the current tick start with no growth
+        update(
+            &mut ticks,
+            2,
+            1,
+            1,
+            1.into(),
+            2.into(),
+            3.into(),
+            4,
+            5,
+            false,
+            u128::MAX,
+        )
+        .unwrap();
+        let info = ticks.get(&2).unwrap();
+        assert_eq!(info.fee_growth_outside_0_x128, 0.into());
+        assert_eq!(info.fee_growth_outside_1_x128, 0.into());
+        assert_eq!(info.seconds_per_liquidity_outside_x128, 0.into());
+        assert_eq!(info.tick_cumulative_outside, 0);
+        assert_eq!(info.seconds_outside, 0);
+    }
+
+    #[ink_lang::test]
+    fn clear_deletes_all_tick_data() {
+        let mut ticks = Ticks::default();
+        update_simple(&mut ticks, 2, 3, false, 10).unwrap();
+        clear(&mut ticks, 2);
+        assert_eq!(ticks.get(&2), None);
+    }
+
+    #[ink_lang::test]
+    fn cross_flips_growth_variables() {
+        let mut ticks = Ticks::default();
+        ticks.insert(
+            &2,
+            &Tick {
+                fee_growth_outside_0_x128: 1.into(),
+                fee_growth_outside_1_x128: 2.into(),
+                liquidity_gross: 3,
+                liquidity_net: 4,
+                seconds_per_liquidity_outside_x128: 5.into(),
+                tick_cumulative_outside: 6,
+                seconds_outside: 7,
+                initialized: true,
+            },
+        );
+        assert_eq!(
+            cross(&mut ticks, 2, 7.into(), 9.into(), 8.into(), 15, 10),
+            4
+        );
+        let info = ticks.get(&2).unwrap();
+        assert_eq!(info.fee_growth_outside_0_x128, 6.into());
+        assert_eq!(info.fee_growth_outside_1_x128, 7.into());
+        assert_eq!(info.seconds_per_liquidity_outside_x128, 3.into());
+        assert_eq!(info.tick_cumulative_outside, 9);
+        assert_eq!(info.seconds_outside, 3);
+
+        // crossing back restores the original values
+        cross(&mut ticks, 2, 7.into(), 9.into(), 8.into(), 15, 10);
+        let info = ticks.get(&2).unwrap();
+        assert_eq!(info.fee_growth_outside_0_x128, 1.into());
+        assert_eq!(info.fee_growth_outside_1_x128, 2.into());
+        assert_eq!(info.seconds_per_liquidity_outside_x128, 5.into());
+        assert_eq!(info.tick_cumulative_outside, 6);
+        assert_eq!(info.seconds_outside, 7);
+    }
+}
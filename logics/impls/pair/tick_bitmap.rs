@@ -0,0 +1,284 @@
+use crate::{
+    ensure,
+    traits::{
+        pair::PairError,
+        types::WrappedU256,
+    },
+};
+use openbrush::storage::Mapping;
+use primitive_types::U256;
+
+/// Packed tick initialized state: every word holds 256 compressed ticks.
+///
+/// Please refer https://github.com/Uniswap/v3-core/blob/main/contracts/libraries/TickBitmap.sol
+pub type TickBitmap = Mapping<i16, WrappedU256>;
+
+/// Computes the position in the bitmap where the initialized bit for a compressed tick lives.
+#[inline]
+pub fn position(tick: i32) -> (i16, u8) {
+    ((tick >> 8) as i16, (tick & 0xff) as u8)
+}
+
+/// Flips the initialized state for a given tick from false to true, or vice versa.
+pub fn flip_tick(bitmap: &mut TickBitmap, tick: i32, tick_spacing: i32) -> Result<(), PairError> {
+    ensure!(tick % tick_spacing == 0, PairError::TickNotSpaced);
+    let (word_pos, bit_pos) = position(tick / tick_spacing);
+    let mask = U256::one() << bit_pos;
+    let word: U256 = bitmap.get(&word_pos).unwrap_or_default().into();
+    bitmap.insert(&word_pos, &(word ^ mask).into());
+    Ok(())
+}
+
+/// Returns the next initialized tick contained in the same word (or adjacent word) as the tick that is either
+/// to the left (less than or equal to) or right (greater than) of the given tick.
+///
+/// Returns `(next, initialized)`, where `next` is at most 256 compressed ticks away from `tick`
+/// and `initialized` tells whether it is an initialized tick or just the word boundary.
+pub fn next_initialized_tick_within_one_word(
+    bitmap: &TickBitmap,
+    tick: i32,
+    tick_spacing: i32,
+    lte: bool,
+) -> (i32, bool) {
+    let mut compressed = tick / tick_spacing;
+    // round towards negative infinity
+    if tick < 0 && tick % tick_spacing != 0 {
+        compressed -= 1;
+    }
+
+    if lte {
+        let (word_pos, bit_pos) = position(compressed);
+        // all the 1s at or to the right of the current bit_pos
+        let mask = (U256::one() << bit_pos) - 1 + (U256::one() << bit_pos);
+        let masked = U256::from(bitmap.get(&word_pos).unwrap_or_default()) & mask;
+
+        // if there are no initialized ticks to the right of or at the current tick, return rightmost in the word
+        let initialized = !masked.is_zero();
+        let next = if initialized {
+            let most_significant_bit = (masked.bits() - 1) as i32;
+            (compressed - (bit_pos as i32 - most_significant_bit)) * tick_spacing
+        } else {
+            (compressed - bit_pos as i32) * tick_spacing
+        };
+        (next, initialized)
+    } else {
+        // start from the word of the next tick, since the current tick state doesn't matter
+        let (word_pos, bit_pos) = position(compressed + 1);
+        // all the 1s at or to the left of the bit_pos
+        let mask = !((U256::one() << bit_pos) - 1);
+        let masked = U256::from(bitmap.get(&word_pos).unwrap_or_default()) & mask;
+
+        // if there are no initialized ticks to the left of the current tick, return leftmost in the word
+        let initialized = !masked.is_zero();
+        let next = if initialized {
+            let least_significant_bit = masked.trailing_zeros() as i32;
+            (compressed + 1 + (least_significant_bit - bit_pos as i32)) * tick_spacing
+        } else {
+            (compressed + 1 + (u8::MAX - bit_pos) as i32) * tick_spacing
+        };
+        (next, initialized)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ink_prelude::collections::BTreeSet;
+
+    /// Deterministic xorshift generator, enough to drive the property checks below.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn range(&mut self, low: i32, high: i32) -> i32 {
+            low + (self.next() % (high - low + 1) as u64) as i32
+        }
+    }
+
+    fn init_ticks(bitmap: &mut TickBitmap, ticks: &[i32], tick_spacing: i32) {
+        for tick in ticks {
+            flip_tick(bitmap, *tick, tick_spacing).unwrap();
+        }
+    }
+
+    fn is_initialized(bitmap: &TickBitmap, tick: i32) -> bool {
+        let (next, initialized) = next_initialized_tick_within_one_word(bitmap, tick, 1, true);
+        next == tick && initialized
+    }
+
+    /// Walks compressed ticks one by one until an initialized tick or the word boundary is found.
+    fn naive_next(
+        initialized: &BTreeSet<i32>,
+        tick: i32,
+        tick_spacing: i32,
+        lte: bool,
+    ) -> (i32, bool) {
+        let compressed = tick.div_euclid(tick_spacing);
+        if lte {
+            let boundary = compressed - (compressed & 0xff);
+            let mut current = compressed;
+            while current >= boundary {
+                if initialized.contains(&(current * tick_spacing)) {
+                    return (current * tick_spacing, true)
+                }
+                current -= 1;
+            }
+            (boundary * tick_spacing, false)
+        } else {
+            let boundary = (compressed + 1) - ((compressed + 1) & 0xff) + 255;
+            let mut current = compressed + 1;
+            while current <= boundary {
+                if initialized.contains(&(current * tick_spacing)) {
+                    return (current * tick_spacing, true)
+                }
+                current += 1;
+            }
+            (boundary * tick_spacing, false)
+        }
+    }
+
+    #[ink_lang::test]
+    fn flip_tick_toggles_only_the_given_tick() {
+        let mut bitmap = TickBitmap::default();
+        assert!(!is_initialized(&bitmap, 1));
+
+        flip_tick(&mut bitmap, -230, 1).unwrap();
+        assert!(is_initialized(&bitmap, -230));
+        assert!(!is_initialized(&bitmap, -231));
+        assert!(!is_initialized(&bitmap, -229));
+        assert!(!is_initialized(&bitmap, -230 + 256));
+        assert!(!is_initialized(&bitmap, -230 - 256));
+
+        flip_tick(&mut bitmap, -230, 1).unwrap();
+        assert!(!is_initialized(&bitmap, -230));
+    }
+
+    #[ink_lang::test]
+    fn flip_tick_rejects_unspaced_ticks() {
+        let mut bitmap = TickBitmap::default();
+        assert_eq!(
+            flip_tick(&mut bitmap, 61, 60),
+            Err(PairError::TickNotSpaced)
+        );
+        assert_eq!(
+            flip_tick(&mut bitmap, -61, 60),
+            Err(PairError::TickNotSpaced)
+        );
+    }
+
+    #[ink_lang::test]
+    fn next_initialized_tick_within_one_word_lte_false() {
+        let mut bitmap = TickBitmap::default();
+        init_ticks(&mut bitmap, &[-200, -55, -4, 70, 78, 84, 139, 240, 535], 1);
+
+        assert_eq!(
+            next_initialized_tick_within_one_word(&bitmap, 78, 1, false),
+            (84, true)
+        );
+        assert_eq!(
+            next_initialized_tick_within_one_word(&bitmap, -55, 1, false),
+            (-4, true)
+        );
+        assert_eq!(
+            next_initialized_tick_within_one_word(&bitmap, 77, 1, false),
+            (78, true)
+        );
+        assert_eq!(
+            next_initialized_tick_within_one_word(&bitmap, -56, 1, false),
+            (-55, true)
+        );
+        assert_eq!(
+            next_initialized_tick_within_one_word(&bitmap, 255, 1, false),
+            (511, false)
+        );
+        assert_eq!(
+            next_initialized_tick_within_one_word(&bitmap, -257, 1, false),
+            (-200, true)
+        );
+        assert_eq!(
+            next_initialized_tick_within_one_word(&bitmap, 340, 1, false),
+            (511, false)
+        );
+        assert_eq!(
+            next_initialized_tick_within_one_word(&bitmap, 328, 1, false),
+            (511, false)
+        );
+    }
+
+    #[ink_lang::test]
+    fn next_initialized_tick_within_one_word_lte_true() {
+        let mut bitmap = TickBitmap::default();
+        init_ticks(&mut bitmap, &[-200, -55, -4, 70, 78, 84, 139, 240, 535], 1);
+
+        assert_eq!(
+            next_initialized_tick_within_one_word(&bitmap, 78, 1, true),
+            (78, true)
+        );
+        assert_eq!(
+            next_initialized_tick_within_one_word(&bitmap, 79, 1, true),
+            (78, true)
+        );
+        assert_eq!(
+            next_initialized_tick_within_one_word(&bitmap, 258, 1, true),
+            (256, false)
+        );
+        assert_eq!(
+            next_initialized_tick_within_one_word(&bitmap, 256, 1, true),
+            (256, false)
+        );
+        assert_eq!(
+            next_initialized_tick_within_one_word(&bitmap, 72, 1, true),
+            (70, true)
+        );
+        assert_eq!(
+            next_initialized_tick_within_one_word(&bitmap, -257, 1, true),
+            (-512, false)
+        );
+        assert_eq!(
+            next_initialized_tick_within_one_word(&bitmap, 1023, 1, true),
+            (768, false)
+        );
+        assert_eq!(
+            next_initialized_tick_within_one_word(&bitmap, 900, 1, true),
+            (768, false)
+        );
+    }
+
+    #[ink_lang::test]
+    fn next_initialized_tick_within_one_word_agrees_with_naive_scan() {
+        let mut rng = Rng(0x9e3779b97f4a7c15);
+        for tick_spacing in [1, 10, 60, 200] {
+            let mut bitmap = TickBitmap::default();
+            let mut initialized = BTreeSet::new();
+            let bound = 1024 * tick_spacing;
+
+            for _ in 0..300 {
+                let tick = rng.range(-bound / tick_spacing, bound / tick_spacing) * tick_spacing;
+                flip_tick(&mut bitmap, tick, tick_spacing).unwrap();
+                if !initialized.remove(&tick) {
+                    initialized.insert(tick);
+                }
+            }
+
+            for _ in 0..1000 {
+                let tick = rng.range(-bound, bound);
+                for lte in [true, false] {
+                    assert_eq!(
+                        next_initialized_tick_within_one_word(&bitmap, tick, tick_spacing, lte),
+                        naive_next(&initialized, tick, tick_spacing, lte),
+                        "tick {} spacing {} lte {}",
+                        tick,
+                        tick_spacing,
+                        lte
+                    );
+                }
+            }
+        }
+    }
+}
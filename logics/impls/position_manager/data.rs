@@ -0,0 +1,17 @@
+pub use crate::traits::position_manager::Position;
+use openbrush::{
+    contracts::psp34::Id,
+    storage::Mapping,
+    traits::AccountId,
+};
+
+pub const STORAGE_KEY: u32 = openbrush::storage_unique_key!(Data);
+
+#[derive(Default, Debug)]
+#[openbrush::upgradeable_storage(STORAGE_KEY)]
+pub struct Data {
+    pub factory: AccountId,
+    /// The id of the last minted position token, ids start at 1.
+    pub last_token_id: u128,
+    pub positions: Mapping<Id, Position>,
+}
@@ -0,0 +1,452 @@
+use crate::helpers::{
+    math::MathError,
+    tick_math::{
+        get_sqrt_ratio_at_tick,
+        MAX_TICK,
+        MIN_TICK,
+    },
+};
+use ink_prelude::{
+    format,
+    string::{
+        String,
+        ToString,
+    },
+    vec::Vec,
+};
+use openbrush::traits::AccountId;
+use primitive_types::U512;
+
+/// Attribute key under which `PSP34Metadata::get_attribute` returns the metadata of a position token.
+pub const TOKEN_URI_KEY: &[u8] = b"token_uri";
+
+/// Name of the protocol the positions are shown under in the metadata.
+pub const PROTOCOL_NAME: &str = "v1-contract";
+
+/// Number of significant figures of the prices shown in the metadata.
+const SIGNIFICANT_FIGURES: u32 = 5;
+
+/// Everything rendered in the metadata of a position token.
+///
+/// Please refer https://github.com/Uniswap/v3-periphery/blob/main/contracts/libraries/NFTDescriptor.sol
+pub struct TokenUriParams {
+    pub token_id: u128,
+    pub pool: AccountId,
+    pub token_0: AccountId,
+    pub token_1: AccountId,
+    /// Empty if the token has no symbol, which is then shown by its address.
+    pub symbol_0: String,
+    pub symbol_1: String,
+    pub decimals_0: u8,
+    pub decimals_1: u8,
+    pub fee: u32,
+    pub tick_spacing: i32,
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+    pub tick_current: i32,
+}
+
+/// Builds the JSON metadata of a position token as a base64 data URI, with the SVG image embedded the same way.
+pub fn token_uri(params: &TokenUriParams) -> Result<String, MathError> {
+    let symbol_0 = escape_json(&display_symbol(&params.symbol_0, &params.token_0));
+    let symbol_1 = escape_json(&display_symbol(&params.symbol_1, &params.token_1));
+    let fee = fee_to_percent_string(params.fee);
+    let price_lower = tick_to_decimal_string(
+        params.tick_lower,
+        params.tick_spacing,
+        params.decimals_0,
+        params.decimals_1,
+    )?;
+    let price_upper = tick_to_decimal_string(
+        params.tick_upper,
+        params.tick_spacing,
+        params.decimals_0,
+        params.decimals_1,
+    )?;
+    let in_range = is_in_range(params);
+
+    let name =
+        format!("{PROTOCOL_NAME} - {fee} - {symbol_0}/{symbol_1} - {price_lower}<>{price_upper}");
+    let description = format!(
+        "This NFT represents a liquidity position in a {PROTOCOL_NAME} {symbol_0}-{symbol_1} pool. \
+         The owner of this NFT can modify or redeem the position.\\n\
+         \\nPool Address: 0x{}\\n{symbol_0} Address: 0x{}\\n{symbol_1} Address: 0x{}\\n\
+         Fee Tier: {fee}\\nToken ID: {}",
+        account_hex(&params.pool),
+        account_hex(&params.token_0),
+        account_hex(&params.token_1),
+        params.token_id,
+    );
+    let image = base64(generate_svg(params, &price_lower, &price_upper, in_range).as_bytes());
+    let json = format!(
+        "{{\"name\":\"{name}\",\"description\":\"{description}\",\
+         \"image\":\"data:image/svg+xml;base64,{image}\",\
+         \"attributes\":[\
+         {{\"trait_type\":\"Token 0\",\"value\":\"{symbol_0}\"}},\
+         {{\"trait_type\":\"Token 1\",\"value\":\"{symbol_1}\"}},\
+         {{\"trait_type\":\"Fee Tier\",\"value\":\"{fee}\"}},\
+         {{\"trait_type\":\"Min Price\",\"value\":\"{price_lower}\"}},\
+         {{\"trait_type\":\"Max Price\",\"value\":\"{price_upper}\"}},\
+         {{\"trait_type\":\"In Range\",\"value\":{in_range}}}]}}"
+    );
+    Ok(format!(
+        "data:application/json;base64,{}",
+        base64(json.as_bytes())
+    ))
+}
+
+/// Whether the current tick of the pool is within the range of the position, i.e. the position earns fees.
+pub fn is_in_range(params: &TokenUriParams) -> bool {
+    params.tick_lower <= params.tick_current && params.tick_current < params.tick_upper
+}
+
+/// Formats a fee in hundredths of a bip as a percentage, e.g. 3000 as "0.3%".
+pub fn fee_to_percent_string(fee: u32) -> String {
+    let integer = fee / 10_000;
+    let fraction = fee % 10_000;
+    if fraction == 0 {
+        return format!("{integer}%")
+    }
+    let fraction = format!("{fraction:04}");
+    format!("{integer}.{}%", fraction.trim_end_matches('0'))
+}
+
+/// Formats the price of token0 in token1 at a tick, adjusted by the decimals of both tokens,
+/// with 5 significant figures.
+///
+/// The usable tick bounds of the pool's tick spacing are shown as "MIN" and "MAX".
+pub fn tick_to_decimal_string(
+    tick: i32,
+    tick_spacing: i32,
+    decimals_0: u8,
+    decimals_1: u8,
+) -> Result<String, MathError> {
+    if tick_spacing != 0 {
+        if tick == (MIN_TICK / tick_spacing) * tick_spacing {
+            return Ok("MIN".to_string())
+        }
+        if tick == (MAX_TICK / tick_spacing) * tick_spacing {
+            return Ok("MAX".to_string())
+        }
+    }
+
+    // price = sqrt_ratio^2 / 2^192 * 10^decimals_0 / 10^decimals_1
+    let sqrt_ratio_x96 = get_sqrt_ratio_at_tick(tick)?;
+    let numerator = sqrt_ratio_x96
+        .full_mul(sqrt_ratio_x96)
+        .checked_mul(
+            U512::from(10)
+                .checked_pow(decimals_0.into())
+                .ok_or(MathError::Overflow)?,
+        )
+        .ok_or(MathError::Overflow)?;
+    let denominator = (U512::one() << 192)
+        .checked_mul(
+            U512::from(10)
+                .checked_pow(decimals_1.into())
+                .ok_or(MathError::Overflow)?,
+        )
+        .ok_or(MathError::Overflow)?;
+    format_significant(numerator, denominator)
+}
+
+/// Formats `numerator / denominator` in decimal with `SIGNIFICANT_FIGURES` significant figures,
+/// rounding half up and omitting trailing zeros of the fractional part.
+fn format_significant(mut numerator: U512, mut denominator: U512) -> Result<String, MathError> {
+    if numerator.is_zero() {
+        return Ok("0".to_string())
+    }
+    let lower = U512::from(10).pow(U512::from(SIGNIFICANT_FIGURES - 1));
+    let upper = lower * 10;
+
+    // scale so that lower <= numerator / denominator < upper, i.e. value = digits * 10^exponent
+    let mut exponent: i32 = 0;
+    while numerator / denominator >= upper {
+        denominator = denominator
+            .checked_mul(10.into())
+            .ok_or(MathError::Overflow)?;
+        exponent += 1;
+    }
+    while numerator / denominator < lower {
+        numerator = numerator
+            .checked_mul(10.into())
+            .ok_or(MathError::Overflow)?;
+        exponent -= 1;
+    }
+    let mut digits = (numerator + denominator / 2) / denominator;
+    if digits == upper {
+        digits = lower;
+        exponent += 1;
+    }
+
+    let digits = digits.low_u64().to_string();
+    let point = SIGNIFICANT_FIGURES as i32 + exponent;
+    let formatted = if exponent >= 0 {
+        format!("{digits}{}", "0".repeat(exponent as usize))
+    } else if point > 0 {
+        let (integer, fraction) = digits.split_at(point as usize);
+        format!("{integer}.{fraction}")
+    } else {
+        format!("0.{}{digits}", "0".repeat(-point as usize))
+    };
+    if exponent < 0 {
+        Ok(formatted
+            .trim_end_matches('0')
+            .trim_end_matches('.')
+            .to_string())
+    } else {
+        Ok(formatted)
+    }
+}
+
+/// Renders the image of a position, the colors are derived from the token addresses.
+pub fn generate_svg(
+    params: &TokenUriParams,
+    price_lower: &str,
+    price_upper: &str,
+    in_range: bool,
+) -> String {
+    let color_0 = token_color(&params.token_0);
+    let color_1 = token_color(&params.token_1);
+    let symbol_0 = escape_xml(&display_symbol(&params.symbol_0, &params.token_0));
+    let symbol_1 = escape_xml(&display_symbol(&params.symbol_1, &params.token_1));
+    let fee = fee_to_percent_string(params.fee);
+    let (status, status_color) = if in_range {
+        ("In range", "#3fb950")
+    } else {
+        ("Out of range", "#d29922")
+    };
+    format!(
+        "<svg width=\"290\" height=\"500\" viewBox=\"0 0 290 500\" xmlns=\"http://www.w3.org/2000/svg\">\
+         <defs><linearGradient id=\"bg\" x1=\"0\" y1=\"0\" x2=\"1\" y2=\"1\">\
+         <stop offset=\"0\" stop-color=\"#{color_0}\"/><stop offset=\"1\" stop-color=\"#{color_1}\"/>\
+         </linearGradient></defs>\
+         <rect width=\"290\" height=\"500\" rx=\"42\" ry=\"42\" fill=\"url(#bg)\"/>\
+         <rect x=\"16\" y=\"16\" width=\"258\" height=\"468\" rx=\"26\" ry=\"26\" fill=\"rgba(0,0,0,0.6)\"/>\
+         <g font-family=\"'Courier New', monospace\" fill=\"white\">\
+         <text x=\"32\" y=\"70\" font-size=\"32\">{symbol_0}/{symbol_1}</text>\
+         <text x=\"32\" y=\"110\" font-size=\"28\">{fee}</text>\
+         <text x=\"32\" y=\"380\" font-size=\"12\">ID: {}</text>\
+         <text x=\"32\" y=\"410\" font-size=\"12\">Min Tick: {} ({price_lower})</text>\
+         <text x=\"32\" y=\"440\" font-size=\"12\">Max Tick: {} ({price_upper})</text>\
+         </g>\
+         <circle cx=\"246\" cy=\"62\" r=\"8\" fill=\"{status_color}\"/>\
+         <text x=\"234\" y=\"66\" font-family=\"'Courier New', monospace\" font-size=\"10\" \
+         fill=\"{status_color}\" text-anchor=\"end\">{status}</text>\
+         </svg>",
+        params.token_id, params.tick_lower, params.tick_upper,
+    )
+}
+
+/// Tokens without a symbol are shown by the beginning of their address.
+fn display_symbol(symbol: &str, token: &AccountId) -> String {
+    if symbol.is_empty() {
+        format!("0x{}", &account_hex(token)[..8])
+    } else {
+        symbol.to_string()
+    }
+}
+
+/// Hex color made of the last three bytes of a token address.
+fn token_color(token: &AccountId) -> String {
+    let bytes: &[u8; 32] = token.as_ref();
+    to_hex(&bytes[29..])
+}
+
+fn account_hex(account: &AccountId) -> String {
+    let bytes: &[u8; 32] = account.as_ref();
+    to_hex(bytes)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn escape_xml(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn escape_json(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Standard base64 encoding with padding.
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = Vec::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = (chunk[0] as u32) << 16
+            | (*chunk.get(1).unwrap_or(&0) as u32) << 8
+            | *chunk.get(2).unwrap_or(&0) as u32;
+        encoded.push(ALPHABET[(n >> 18) as usize & 63]);
+        encoded.push(ALPHABET[(n >> 12) as usize & 63]);
+        encoded.push(if chunk.len() > 1 {
+            ALPHABET[(n >> 6) as usize & 63]
+        } else {
+            b'='
+        });
+        encoded.push(if chunk.len() > 2 {
+            ALPHABET[n as usize & 63]
+        } else {
+            b'='
+        });
+    }
+    // the alphabet is ASCII only
+    String::from_utf8(encoded).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params() -> TokenUriParams {
+        TokenUriParams {
+            token_id: 1,
+            pool: AccountId::from([0x01; 32]),
+            token_0: AccountId::from([0x02; 32]),
+            token_1: AccountId::from([0x03; 32]),
+            symbol_0: "AAA".to_string(),
+            symbol_1: "B<B".to_string(),
+            decimals_0: 18,
+            decimals_1: 18,
+            fee: 3000,
+            tick_spacing: 60,
+            tick_lower: -60,
+            tick_upper: 60,
+            tick_current: 0,
+        }
+    }
+
+    #[test]
+    fn fee_to_percent_string_works() {
+        assert_eq!(fee_to_percent_string(100), "0.01%");
+        assert_eq!(fee_to_percent_string(500), "0.05%");
+        assert_eq!(fee_to_percent_string(3000), "0.3%");
+        assert_eq!(fee_to_percent_string(10000), "1%");
+        assert_eq!(fee_to_percent_string(12345), "1.2345%");
+        assert_eq!(fee_to_percent_string(0), "0%");
+    }
+
+    #[test]
+    fn tick_to_decimal_string_works() {
+        assert_eq!(tick_to_decimal_string(0, 60, 18, 18), Ok("1".to_string()));
+        assert_eq!(
+            tick_to_decimal_string(0, 60, 18, 6),
+            Ok("1000000000000".to_string())
+        );
+        assert_eq!(
+            tick_to_decimal_string(0, 60, 6, 18),
+            Ok("0.000000000001".to_string())
+        );
+        // 1.0001^60 and 1.0001^-60
+        assert_eq!(
+            tick_to_decimal_string(60, 60, 18, 18),
+            Ok("1.006".to_string())
+        );
+        assert_eq!(
+            tick_to_decimal_string(-60, 60, 18, 18),
+            Ok("0.99402".to_string())
+        );
+        assert_eq!(
+            tick_to_decimal_string(1, 1, 18, 18),
+            Ok("1.0001".to_string())
+        );
+        // 1.0001^46054 ~= 100.0
+        assert_eq!(
+            tick_to_decimal_string(46054, 1, 18, 18),
+            Ok("100".to_string())
+        );
+    }
+
+    #[test]
+    fn tick_to_decimal_string_shows_usable_bounds() {
+        assert_eq!(
+            tick_to_decimal_string(-887220, 60, 18, 18),
+            Ok("MIN".to_string())
+        );
+        assert_eq!(
+            tick_to_decimal_string(887220, 60, 18, 18),
+            Ok("MAX".to_string())
+        );
+        assert_ne!(
+            tick_to_decimal_string(-887220, 10, 18, 18),
+            Ok("MIN".to_string())
+        );
+    }
+
+    #[test]
+    fn tick_to_decimal_string_fails_on_huge_decimals() {
+        assert_eq!(
+            tick_to_decimal_string(0, 60, 155, 18),
+            Err(MathError::Overflow)
+        );
+        assert_eq!(
+            tick_to_decimal_string(0, 60, 18, 255),
+            Err(MathError::Overflow)
+        );
+    }
+
+    #[test]
+    fn base64_works() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"a"), "YQ==");
+        assert_eq!(base64(b"ab"), "YWI=");
+        assert_eq!(base64(b"abc"), "YWJj");
+        assert_eq!(base64(b"hello world"), "aGVsbG8gd29ybGQ=");
+    }
+
+    #[test]
+    fn is_in_range_works() {
+        let mut params = params();
+        assert!(is_in_range(&params));
+        params.tick_current = 60;
+        assert!(!is_in_range(&params));
+        params.tick_current = -60;
+        assert!(is_in_range(&params));
+        params.tick_current = -61;
+        assert!(!is_in_range(&params));
+    }
+
+    #[test]
+    fn token_uri_is_a_base64_json_data_uri() {
+        let params = params();
+        let uri = token_uri(&params).unwrap();
+        assert!(uri.starts_with("data:application/json;base64,"));
+        // deterministic for the same position
+        assert_eq!(token_uri(&params).unwrap(), uri);
+
+        let svg = generate_svg(&params, "0.99402", "1.006", true);
+        assert!(svg.contains("AAA/B&lt;B"));
+        assert!(svg.contains("0.3%"));
+        assert!(svg.contains("In range"));
+        assert!(svg.contains("#020202"));
+
+        let unnamed = TokenUriParams {
+            symbol_0: String::new(),
+            ..params
+        };
+        let svg = generate_svg(&unnamed, "0.99402", "1.006", false);
+        assert!(svg.contains("0x02020202/B&lt;B"));
+        assert!(svg.contains("Out of range"));
+    }
+}
@@ -0,0 +1,3 @@
+pub mod data;
+pub mod descriptor;
+pub mod position_manager;
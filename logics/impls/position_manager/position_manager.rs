@@ -0,0 +1,571 @@
+use crate::{
+    ensure,
+    helpers::{
+        liquidity_amounts::get_liquidity_for_amounts,
+        tick_math::get_sqrt_ratio_at_tick,
+        transfer_helper::safe_transfer_from,
+    },
+    impls::{
+        pair::position::owed,
+        position_manager::descriptor::{
+            token_uri,
+            TokenUriParams,
+        },
+        router::router::ensure,
+    },
+    traits::{
+        callback::MintCallback,
+        factory::FactoryRef,
+        pair::{
+            PairError,
+            PoolRef,
+        },
+        types::WrappedU256,
+    },
+};
+pub use crate::{
+    impls::position_manager::*,
+    traits::position_manager::*,
+};
+use ink_env::CallFlags;
+use ink_prelude::{
+    string::String,
+    vec::Vec,
+};
+use openbrush::{
+    contracts::{
+        psp34::*,
+        traits::psp22::extensions::metadata::PSP22MetadataRef,
+    },
+    modifier_definition,
+    modifiers,
+    traits::{
+        AccountId,
+        Balance,
+        Storage,
+    },
+};
+use primitive_types::U256;
+use scale::{
+    Decode,
+    Encode,
+};
+
+/// Parameters of `Internal::_add_liquidity`.
+pub struct AddLiquidityParams {
+    pub token_0: AccountId,
+    pub token_1: AccountId,
+    pub fee: u32,
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+    pub amount_0_desired: Balance,
+    pub amount_1_desired: Balance,
+    pub amount_0_min: Balance,
+    pub amount_1_min: Balance,
+}
+
+/// Passed through `Pair::mint` so that the callback knows which pool to expect and who pays.
+#[derive(Encode, Decode)]
+struct MintCallbackData {
+    token_0: AccountId,
+    token_1: AccountId,
+    fee: u32,
+    payer: AccountId,
+}
+
+pub trait Internal {
+    /// Returns the pool for the given tokens and fee, failing if it does not exist.
+    fn _get_pool(
+        &self,
+        token_0: AccountId,
+        token_1: AccountId,
+        fee: u32,
+    ) -> Result<AccountId, PositionManagerError>;
+
+    /// Returns the pool for the given tokens and fee, creating and initializing it if necessary.
+    fn _create_and_initialize_pool_if_necessary(
+        &mut self,
+        token_0: AccountId,
+        token_1: AccountId,
+        fee: u32,
+        sqrt_price_x96: WrappedU256,
+    ) -> Result<AccountId, PositionManagerError>;
+
+    /// Adds liquidity to an initialized pool on behalf of the manager, paid by the caller.
+    ///
+    /// Returns the pool, the liquidity added and the amounts of token0/token1 paid.
+    fn _add_liquidity(
+        &mut self,
+        params: AddLiquidityParams,
+    ) -> Result<(AccountId, u128, Balance, Balance), PositionManagerError>;
+
+    fn _emit_increase_liquidity_event(
+        &self,
+        _token_id: Id,
+        _liquidity: u128,
+        _amount_0: Balance,
+        _amount_1: Balance,
+    );
+
+    fn _emit_decrease_liquidity_event(
+        &self,
+        _token_id: Id,
+        _liquidity: u128,
+        _amount_0: Balance,
+        _amount_1: Balance,
+    );
+
+    fn _emit_collect_event(
+        &self,
+        _token_id: Id,
+        _recipient: AccountId,
+        _amount_0: Balance,
+        _amount_1: Balance,
+    );
+}
+
+impl<T: Storage<data::Data> + Storage<psp34::Data>> PositionManager for T {
+    default fn factory(&self) -> AccountId {
+        self.data::<data::Data>().factory
+    }
+
+    default fn positions(&self, token_id: Id) -> Option<data::Position> {
+        self.data::<data::Data>().positions.get(&token_id)
+    }
+
+    default fn mint(
+        &mut self,
+        params: MintParams,
+    ) -> Result<(Id, u128, Balance, Balance), PositionManagerError> {
+        ensure!(
+            params.token_0 < params.token_1,
+            PositionManagerError::TokensNotSorted
+        );
+        self._create_and_initialize_pool_if_necessary(
+            params.token_0,
+            params.token_1,
+            params.fee,
+            params.sqrt_price_x96,
+        )?;
+
+        let (pool, liquidity, amount_0, amount_1) = self._add_liquidity(AddLiquidityParams {
+            token_0: params.token_0,
+            token_1: params.token_1,
+            fee: params.fee,
+            tick_lower: params.tick_lower,
+            tick_upper: params.tick_upper,
+            amount_0_desired: params.amount_0_desired,
+            amount_1_desired: params.amount_1_desired,
+            amount_0_min: params.amount_0_min,
+            amount_1_min: params.amount_1_min,
+        })?;
+
+        let token_id = self.data::<data::Data>().last_token_id + 1;
+        self.data::<data::Data>().last_token_id = token_id;
+        let token_id = Id::U128(token_id);
+        psp34::Internal::_mint_to(self, params.recipient, token_id.clone())?;
+
+        let pool_position = PoolRef::positions(
+            &pool,
+            Self::env().account_id(),
+            params.tick_lower,
+            params.tick_upper,
+        );
+        self.data::<data::Data>().positions.insert(
+            &token_id,
+            &data::Position {
+                token_0: params.token_0,
+                token_1: params.token_1,
+                fee: params.fee,
+                tick_lower: params.tick_lower,
+                tick_upper: params.tick_upper,
+                liquidity,
+                fee_growth_inside_0_last_x128: pool_position.fee_growth_inside_0_last_x128,
+                fee_growth_inside_1_last_x128: pool_position.fee_growth_inside_1_last_x128,
+                tokens_owed_0: 0,
+                tokens_owed_1: 0,
+            },
+        );
+
+        self._emit_increase_liquidity_event(token_id.clone(), liquidity, amount_0, amount_1);
+        Ok((token_id, liquidity, amount_0, amount_1))
+    }
+
+    #[modifiers(ensure(deadline), is_authorized_for_token(token_id))]
+    default fn increase_liquidity(
+        &mut self,
+        token_id: Id,
+        amount_0_desired: Balance,
+        amount_1_desired: Balance,
+        amount_0_min: Balance,
+        amount_1_min: Balance,
+        deadline: u64,
+    ) -> Result<(u128, Balance, Balance), PositionManagerError> {
+        let mut position = self
+            .data::<data::Data>()
+            .positions
+            .get(&token_id)
+            .ok_or(PositionManagerError::TokenNotFound)?;
+
+        let (pool, liquidity, amount_0, amount_1) = self._add_liquidity(AddLiquidityParams {
+            token_0: position.token_0,
+            token_1: position.token_1,
+            fee: position.fee,
+            tick_lower: position.tick_lower,
+            tick_upper: position.tick_upper,
+            amount_0_desired,
+            amount_1_desired,
+            amount_0_min,
+            amount_1_min,
+        })?;
+
+        // this is now updated to the current transaction
+        update_fees_owed(&mut position, pool)?;
+        position.liquidity = position
+            .liquidity
+            .checked_add(liquidity)
+            .ok_or(PositionManagerError::InsufficientLiquidity)?;
+        self.data::<data::Data>()
+            .positions
+            .insert(&token_id, &position);
+
+        self._emit_increase_liquidity_event(token_id, liquidity, amount_0, amount_1);
+        Ok((liquidity, amount_0, amount_1))
+    }
+
+    #[modifiers(ensure(deadline), is_authorized_for_token(token_id))]
+    default fn decrease_liquidity(
+        &mut self,
+        token_id: Id,
+        liquidity: u128,
+        amount_0_min: Balance,
+        amount_1_min: Balance,
+        deadline: u64,
+    ) -> Result<(Balance, Balance), PositionManagerError> {
+        ensure!(liquidity > 0, PositionManagerError::ZeroLiquidity);
+        let mut position = self
+            .data::<data::Data>()
+            .positions
+            .get(&token_id)
+            .ok_or(PositionManagerError::TokenNotFound)?;
+        ensure!(
+            position.liquidity >= liquidity,
+            PositionManagerError::InsufficientLiquidity
+        );
+
+        let pool = self._get_pool(position.token_0, position.token_1, position.fee)?;
+        let (amount_0, amount_1) =
+            PoolRef::burn(&pool, position.tick_lower, position.tick_upper, liquidity)?;
+        ensure!(
+            amount_0 >= amount_0_min && amount_1 >= amount_1_min,
+            PositionManagerError::PriceSlippageCheck
+        );
+
+        // the burned amounts are owed to the position on top of the fees earned until now
+        update_fees_owed(&mut position, pool)?;
+        position.tokens_owed_0 = position.tokens_owed_0.wrapping_add(amount_0);
+        position.tokens_owed_1 = position.tokens_owed_1.wrapping_add(amount_1);
+        // subtraction is safe because we checked position.liquidity is gte liquidity
+        position.liquidity -= liquidity;
+        self.data::<data::Data>()
+            .positions
+            .insert(&token_id, &position);
+
+        self._emit_decrease_liquidity_event(token_id, liquidity, amount_0, amount_1);
+        Ok((amount_0, amount_1))
+    }
+
+    #[modifiers(is_authorized_for_token(token_id))]
+    default fn collect(
+        &mut self,
+        token_id: Id,
+        recipient: AccountId,
+        amount_0_max: Balance,
+        amount_1_max: Balance,
+    ) -> Result<(Balance, Balance), PositionManagerError> {
+        ensure!(
+            amount_0_max > 0 || amount_1_max > 0,
+            PositionManagerError::NothingToCollect
+        );
+        let mut position = self
+            .data::<data::Data>()
+            .positions
+            .get(&token_id)
+            .ok_or(PositionManagerError::TokenNotFound)?;
+        let pool = self._get_pool(position.token_0, position.token_1, position.fee)?;
+
+        // trigger an update of the position fees owed and fee growth snapshots if it has any liquidity
+        if position.liquidity > 0 {
+            PoolRef::burn(&pool, position.tick_lower, position.tick_upper, 0)?;
+            update_fees_owed(&mut position, pool)?;
+        }
+
+        // compute the arguments to give to the pool collect method
+        let amount_0_collect = amount_0_max.min(position.tokens_owed_0);
+        let amount_1_collect = amount_1_max.min(position.tokens_owed_1);
+
+        // the actual amounts collected are returned
+        let (amount_0, amount_1) = PoolRef::collect(
+            &pool,
+            recipient,
+            position.tick_lower,
+            position.tick_upper,
+            amount_0_collect,
+            amount_1_collect,
+        )?;
+
+        // sometimes there will be a few less wei than expected due to rounding down in core, but we just subtract
+        // the full amount expected instead of the actual amount so we can burn the token
+        position.tokens_owed_0 -= amount_0_collect;
+        position.tokens_owed_1 -= amount_1_collect;
+        self.data::<data::Data>()
+            .positions
+            .insert(&token_id, &position);
+
+        self._emit_collect_event(token_id, recipient, amount_0, amount_1);
+        Ok((amount_0, amount_1))
+    }
+
+    #[modifiers(is_authorized_for_token(token_id))]
+    default fn burn(&mut self, token_id: Id) -> Result<(), PositionManagerError> {
+        let position = self
+            .data::<data::Data>()
+            .positions
+            .get(&token_id)
+            .ok_or(PositionManagerError::TokenNotFound)?;
+        ensure!(
+            position.liquidity == 0 && position.tokens_owed_0 == 0 && position.tokens_owed_1 == 0,
+            PositionManagerError::NotCleared
+        );
+        self.data::<data::Data>().positions.remove(&token_id);
+
+        let owner = self
+            .owner_of(token_id.clone())
+            .ok_or(PositionManagerError::TokenNotFound)?;
+        psp34::Internal::_burn_from(self, owner, token_id)?;
+        Ok(())
+    }
+
+    default fn token_uri(&self, token_id: Id) -> Result<String, PositionManagerError> {
+        let position = self
+            .data::<data::Data>()
+            .positions
+            .get(&token_id)
+            .ok_or(PositionManagerError::TokenNotFound)?;
+        let token_id = match token_id {
+            Id::U128(token_id) => token_id,
+            _ => return Err(PositionManagerError::TokenNotFound),
+        };
+        let pool = self._get_pool(position.token_0, position.token_1, position.fee)?;
+
+        let params = TokenUriParams {
+            token_id,
+            pool,
+            token_0: position.token_0,
+            token_1: position.token_1,
+            symbol_0: token_symbol(position.token_0),
+            symbol_1: token_symbol(position.token_1),
+            decimals_0: token_decimals(position.token_0),
+            decimals_1: token_decimals(position.token_1),
+            fee: position.fee,
+            tick_spacing: PoolRef::tick_spacing(&pool),
+            tick_lower: position.tick_lower,
+            tick_upper: position.tick_upper,
+            tick_current: PoolRef::slot0(&pool).tick,
+        };
+        Ok(token_uri(&params)?)
+    }
+}
+
+impl<T: Storage<data::Data>> MintCallback for T {
+    default fn mint_callback(
+        &mut self,
+        amount_0_owed: Balance,
+        amount_1_owed: Balance,
+        data: Vec<u8>,
+    ) -> Result<(), PairError> {
+        let decoded =
+            MintCallbackData::decode(&mut &data[..]).map_err(|_| PairError::InvalidCallbackData)?;
+        let pool = Self::env().caller();
+        ensure!(
+            FactoryRef::get_pool(
+                &self.data::<data::Data>().factory,
+                decoded.token_0,
+                decoded.token_1,
+                decoded.fee
+            ) == Some(pool),
+            PairError::InvalidCallbackCaller
+        );
+
+        if amount_0_owed > 0 {
+            safe_transfer_from(decoded.token_0, decoded.payer, pool, amount_0_owed)?;
+        }
+        if amount_1_owed > 0 {
+            safe_transfer_from(decoded.token_1, decoded.payer, pool, amount_1_owed)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: Storage<data::Data>> Internal for T {
+    default fn _get_pool(
+        &self,
+        token_0: AccountId,
+        token_1: AccountId,
+        fee: u32,
+    ) -> Result<AccountId, PositionManagerError> {
+        FactoryRef::get_pool(&self.data::<data::Data>().factory, token_0, token_1, fee)
+            .ok_or(PositionManagerError::PoolNotFound)
+    }
+
+    default fn _create_and_initialize_pool_if_necessary(
+        &mut self,
+        token_0: AccountId,
+        token_1: AccountId,
+        fee: u32,
+        sqrt_price_x96: WrappedU256,
+    ) -> Result<AccountId, PositionManagerError> {
+        let factory = self.data::<data::Data>().factory;
+        let pool = match FactoryRef::get_pool(&factory, token_0, token_1, fee) {
+            Some(pool) => pool,
+            None => FactoryRef::create_pool(&factory, token_0, token_1, fee)?,
+        };
+        if U256::from(PoolRef::slot0(&pool).sqrt_price_x96).is_zero() {
+            PoolRef::initialize(&pool, sqrt_price_x96)?;
+        }
+        Ok(pool)
+    }
+
+    default fn _add_liquidity(
+        &mut self,
+        params: AddLiquidityParams,
+    ) -> Result<(AccountId, u128, Balance, Balance), PositionManagerError> {
+        let pool = self._get_pool(params.token_0, params.token_1, params.fee)?;
+
+        // compute the liquidity amount
+        let liquidity = get_liquidity_for_amounts(
+            PoolRef::slot0(&pool).sqrt_price_x96.into(),
+            get_sqrt_ratio_at_tick(params.tick_lower)?,
+            get_sqrt_ratio_at_tick(params.tick_upper)?,
+            params.amount_0_desired,
+            params.amount_1_desired,
+        )?;
+
+        let callback_data = MintCallbackData {
+            token_0: params.token_0,
+            token_1: params.token_1,
+            fee: params.fee,
+            payer: Self::env().caller(),
+        };
+        let (amount_0, amount_1) = PoolRef::mint_builder(
+            &pool,
+            Self::env().account_id(),
+            params.tick_lower,
+            params.tick_upper,
+            liquidity,
+            callback_data.encode(),
+        )
+        .call_flags(CallFlags::default().set_allow_reentry(true))
+        .fire()
+        .map_err(|_| PositionManagerError::PoolCallFailed)??;
+
+        ensure!(
+            amount_0 >= params.amount_0_min && amount_1 >= params.amount_1_min,
+            PositionManagerError::PriceSlippageCheck
+        );
+        Ok((pool, liquidity, amount_0, amount_1))
+    }
+
+    default fn _emit_increase_liquidity_event(
+        &self,
+        _token_id: Id,
+        _liquidity: u128,
+        _amount_0: Balance,
+        _amount_1: Balance,
+    ) {
+    }
+
+    default fn _emit_decrease_liquidity_event(
+        &self,
+        _token_id: Id,
+        _liquidity: u128,
+        _amount_0: Balance,
+        _amount_1: Balance,
+    ) {
+    }
+
+    default fn _emit_collect_event(
+        &self,
+        _token_id: Id,
+        _recipient: AccountId,
+        _amount_0: Balance,
+        _amount_1: Balance,
+    ) {
+    }
+}
+
+/// Only the owner of the token or an operator approved for it may manage the position.
+#[modifier_definition]
+pub fn is_authorized_for_token<T, F, R, E>(instance: &mut T, body: F, token_id: Id) -> Result<R, E>
+where
+    T: Storage<psp34::Data>,
+    F: FnOnce(&mut T) -> Result<R, E>,
+    E: From<PositionManagerError>,
+{
+    let caller = T::env().caller();
+    let owner = instance
+        .owner_of(token_id.clone())
+        .ok_or(PositionManagerError::TokenNotFound)?;
+    ensure!(
+        owner == caller || instance.allowance(owner, caller, Some(token_id)),
+        PositionManagerError::NotApproved
+    );
+    body(instance)
+}
+
+/// The symbol of a token, empty if the token does not implement `PSP22Metadata`.
+///
+/// The call must not trap the caller, metadata is optional for PSP22 tokens.
+fn token_symbol(token: AccountId) -> String {
+    PSP22MetadataRef::token_symbol_builder(&token)
+        .fire()
+        .ok()
+        .flatten()
+        .unwrap_or_default()
+}
+
+/// The decimals of a token, 0 if the token does not implement `PSP22Metadata`.
+fn token_decimals(token: AccountId) -> u8 {
+    PSP22MetadataRef::token_decimals_builder(&token)
+        .fire()
+        .unwrap_or_default()
+}
+
+/// Credits the fees earned since the last checkpoint of the position and moves the checkpoint
+/// to the current fee growth inside of the position's range in the pool.
+fn update_fees_owed(
+    position: &mut data::Position,
+    pool: AccountId,
+) -> Result<(), PositionManagerError> {
+    let pool_position = PoolRef::positions(
+        &pool,
+        ink_env::account_id::<ink_env::DefaultEnvironment>(),
+        position.tick_lower,
+        position.tick_upper,
+    );
+    let tokens_owed_0 = owed(
+        pool_position.fee_growth_inside_0_last_x128.into(),
+        position.fee_growth_inside_0_last_x128.into(),
+        position.liquidity,
+    )?;
+    let tokens_owed_1 = owed(
+        pool_position.fee_growth_inside_1_last_x128.into(),
+        position.fee_growth_inside_1_last_x128.into(),
+        position.liquidity,
+    )?;
+    position.tokens_owed_0 = position.tokens_owed_0.wrapping_add(tokens_owed_0);
+    position.tokens_owed_1 = position.tokens_owed_1.wrapping_add(tokens_owed_1);
+    position.fee_growth_inside_0_last_x128 = pool_position.fee_growth_inside_0_last_x128;
+    position.fee_growth_inside_1_last_x128 = pool_position.fee_growth_inside_1_last_x128;
+    Ok(())
+}
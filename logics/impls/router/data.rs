@@ -1,4 +1,3 @@
-use ink_env::Hash;
 use openbrush::traits::AccountId;
 
 pub const STORAGE_KEY: u32 = openbrush::storage_unique_key!(Data);
@@ -8,5 +7,4 @@ pub const STORAGE_KEY: u32 = openbrush::storage_unique_key!(Data);
 pub struct Data {
     pub factory: AccountId,
     pub wnative: AccountId,
-    pub pair_code_hash: Hash,
 }
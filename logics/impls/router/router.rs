@@ -1,15 +1,9 @@
 use crate::{
     ensure,
     helpers::{
-        helper::{
-            get_amount_in,
-            get_amount_out,
-            get_amounts_in,
-            get_amounts_out,
-            get_reserves,
-            pair_for,
-            quote,
-            sort_tokens,
+        tick_math::{
+            MAX_SQRT_RATIO,
+            MIN_SQRT_RATIO,
         },
         transfer_helper::{
             safe_transfer,
@@ -20,12 +14,23 @@ use crate::{
         },
     },
     traits::{
+        callback::SwapCallback,
         factory::FactoryRef,
-        pair::PoolRef,
+        pair::{
+            PairError,
+            PoolRef,
+        },
+        types::WrappedU256,
     },
 };
-use ink_env::CallFlags;
-use ink_prelude::vec::Vec;
+use ink_env::{
+    CallFlags,
+    DefaultEnvironment,
+};
+use ink_prelude::{
+    vec,
+    vec::Vec,
+};
 use openbrush::{
     contracts::traits::psp22::PSP22Ref,
     modifier_definition,
@@ -36,29 +41,78 @@ use openbrush::{
         Storage,
     },
 };
+use primitive_types::U256;
+use scale::{
+    Decode,
+    Encode,
+};
 
 pub use crate::{
     impls::router::*,
     traits::router::*,
 };
 
-pub trait Internal {
-    fn _add_liquidity(
-        &self,
-        token_a: AccountId,
-        token_b: AccountId,
-        amount_a_desired: Balance,
-        amount_b_desired: Balance,
-        amount_a_min: Balance,
-        amount_b_min: Balance,
-    ) -> Result<(Balance, Balance), RouterError>;
+/// Passed through `Pair::swap` so that the callback knows which pool to expect and who pays.
+///
+/// `path`/`fees` are the hops up to the swapping pool, which is the last one. Only exact output
+/// swaps pass more than one hop, the earlier ones are swapped from the callback to pay the pool.
+#[derive(Encode, Decode)]
+struct SwapCallbackData {
+    path: Vec<AccountId>,
+    fees: Vec<u32>,
+    payer: AccountId,
+}
 
-    fn _swap(
-        &self,
-        amounts: &Vec<Balance>,
+pub trait Internal {
+    /// Swaps `amount_in` of `path[0]` along the path, paid by `payer`, and returns the amount of
+    /// the last token received by `recipient`.
+    ///
+    /// The output of each intermediate hop is received and paid for by the router.
+    fn _exact_input(
+        &mut self,
+        amount_in: Balance,
         path: Vec<AccountId>,
-        to: AccountId,
-    ) -> Result<(), RouterError>;
+        fees: Vec<u32>,
+        payer: AccountId,
+        recipient: AccountId,
+    ) -> Result<Balance, RouterError>;
+
+    /// Swaps `path[0]` paid by `payer` along the path for exactly `amount_out` of the last token
+    /// received by `recipient`, and returns the amount of `path[0]` paid.
+    ///
+    /// The last hop is swapped first, the input of each hop is paid from the callback by swapping
+    /// the previous hop for exactly that amount. The amount paid is measured on the payer's balance.
+    fn _exact_output(
+        &mut self,
+        amount_out: Balance,
+        path: Vec<AccountId>,
+        fees: Vec<u32>,
+        payer: AccountId,
+        recipient: AccountId,
+    ) -> Result<Balance, RouterError>;
+
+    /// Swaps the last hop of `path` for exactly `amount_out` received by `recipient`, and returns
+    /// the amount of the hop's input token owed to the pool.
+    fn _swap_exact_output(
+        &mut self,
+        amount_out: Balance,
+        path: Vec<AccountId>,
+        fees: Vec<u32>,
+        payer: AccountId,
+        recipient: AccountId,
+    ) -> Result<Balance, RouterError>;
+
+    /// Swaps exactly `amount_in` of `token_in` paid by `payer` in a single pool, and returns the
+    /// amount of `token_out` received by `recipient`.
+    fn _swap_exact_input(
+        &mut self,
+        amount_in: Balance,
+        token_in: AccountId,
+        token_out: AccountId,
+        fee: u32,
+        payer: AccountId,
+        recipient: AccountId,
+    ) -> Result<Balance, RouterError>;
 }
 
 impl<T: Storage<data::Data>> Router for T {
@@ -70,177 +124,22 @@ impl<T: Storage<data::Data>> Router for T {
         self.data().wnative
     }
 
-    #[modifiers(ensure(deadline))]
-    default fn add_liquidity(
-        &mut self,
-        token_a: AccountId,
-        token_b: AccountId,
-        amount_a_desired: Balance,
-        amount_b_desired: Balance,
-        amount_a_min: Balance,
-        amount_b_min: Balance,
-        to: AccountId,
-        deadline: u64,
-    ) -> Result<(Balance, Balance, Balance), RouterError> {
-        let (amount_a, amount_b) = self._add_liquidity(
-            token_a,
-            token_b,
-            amount_a_desired,
-            amount_b_desired,
-            amount_a_min,
-            amount_b_min,
-        )?;
-
-        let pair_contract = pair_for(
-            self.data().factory.clone().as_ref(),
-            self.data().pair_code_hash.as_ref(),
-            token_a,
-            token_b,
-        )?;
-
-        let caller = Self::env().caller();
-        safe_transfer_from(token_a, caller, pair_contract, amount_a)?;
-        safe_transfer_from(token_b, caller, pair_contract, amount_b)?;
-
-        let liquidity = PoolRef::mint(&pair_contract, to)?;
-
-        Ok((amount_a, amount_b, liquidity))
-    }
-
-    #[modifiers(ensure(deadline))]
-    default fn add_liquidity_native(
-        &mut self,
-        token: AccountId,
-        amount_token_desired: Balance,
-        amount_token_min: Balance,
-        amount_native_min: Balance,
-        to: AccountId,
-        deadline: u64,
-    ) -> Result<(Balance, Balance, Balance), RouterError> {
-        let wnative = self.data().wnative;
-        let received_value = Self::env().transferred_value();
-        let caller = Self::env().caller();
-        let (amount, amount_native) = self._add_liquidity(
-            token,
-            wnative,
-            amount_token_desired,
-            received_value,
-            amount_token_min,
-            amount_native_min,
-        )?;
-        let pair_contract = pair_for(
-            self.data().factory.clone().as_ref(),
-            self.data().pair_code_hash.as_ref(),
-            token,
-            wnative,
-        )?;
-
-        safe_transfer_from(token, caller, pair_contract, amount)?;
-        wrap(&wnative, amount_native)?;
-        PSP22Ref::transfer(&wnative, pair_contract, amount_native, Vec::<u8>::new())?;
-        let liquidity = PoolRef::mint(&pair_contract, to)?;
-
-        if received_value > amount_native {
-            safe_transfer_native(caller, received_value - amount_native)?
-        }
-        Ok((amount, amount_native, liquidity))
-    }
-
-    #[modifiers(ensure(deadline))]
-    default fn remove_liquidity(
-        &mut self,
-        token_a: AccountId,
-        token_b: AccountId,
-        liquidity: Balance,
-        amount_a_min: Balance,
-        amount_b_min: Balance,
-        to: AccountId,
-        deadline: u64,
-    ) -> Result<(Balance, Balance), RouterError> {
-        let pair_contract = pair_for(
-            self.data().factory.clone().as_ref(),
-            self.data().pair_code_hash.as_ref(),
-            token_a,
-            token_b,
-        )?;
-
-        safe_transfer_from(
-            pair_contract,
-            Self::env().caller(),
-            pair_contract,
-            liquidity,
-        )?;
-
-        let (amount_0, amount_1) = PoolRef::burn_builder(&pair_contract, to)
-            .call_flags(CallFlags::default().set_allow_reentry(true))
-            .fire()
-            .unwrap()?;
-        let (token_0, _) = sort_tokens(token_a, token_b)?;
-        let (amount_a, amount_b) = if token_a == token_0 {
-            (amount_0, amount_1)
-        } else {
-            (amount_1, amount_0)
-        };
-
-        ensure!(amount_a >= amount_a_min, RouterError::InsufficientAAmount);
-        ensure!(amount_b >= amount_b_min, RouterError::InsufficientBAmount);
-
-        Ok((amount_a, amount_b))
-    }
-
-    #[modifiers(ensure(deadline))]
-    default fn remove_liquidity_native(
-        &mut self,
-        token: AccountId,
-        liquidity: Balance,
-        amount_token_min: Balance,
-        amount_native_min: Balance,
-        to: AccountId,
-        deadline: u64,
-    ) -> Result<(Balance, Balance), RouterError> {
-        let wnative = self.data().wnative;
-        let (amount_token, amount_native) = self.remove_liquidity(
-            token,
-            wnative,
-            liquidity,
-            amount_token_min,
-            amount_native_min,
-            Self::env().account_id(),
-            deadline,
-        )?;
-        safe_transfer(token, to, amount_token)?;
-        unwrap(&wnative, amount_native)?;
-        safe_transfer_native(to, amount_native)?;
-        Ok((amount_token, amount_native))
-    }
-
     #[modifiers(ensure(deadline))]
     default fn swap_exact_tokens_for_tokens(
         &mut self,
         amount_in: Balance,
         amount_out_min: Balance,
         path: Vec<AccountId>,
+        fees: Vec<u32>,
         to: AccountId,
         deadline: u64,
-    ) -> Result<Vec<Balance>, RouterError> {
-        let factory = self.data().factory;
-        let pair_hash = self.data().pair_code_hash;
-        let factory_ref = factory.as_ref();
-        let pair_hash_ref = pair_hash.as_ref();
-
-        let amounts = get_amounts_out(factory_ref, pair_hash_ref, amount_in, &path)?;
+    ) -> Result<Balance, RouterError> {
+        let amount_out = self._exact_input(amount_in, path, fees, Self::env().caller(), to)?;
         ensure!(
-            amounts[amounts.len() - 1] >= amount_out_min,
+            amount_out >= amount_out_min,
             RouterError::InsufficientOutputAmount
         );
-        safe_transfer_from(
-            path[0],
-            Self::env().caller(),
-            pair_for(factory_ref, pair_hash_ref, path[0], path[1])?,
-            amounts[0],
-        )?;
-        self._swap(&amounts, path, to)?;
-        Ok(amounts)
+        Ok(amount_out)
     }
 
     #[modifiers(ensure(deadline))]
@@ -249,27 +148,16 @@ impl<T: Storage<data::Data>> Router for T {
         amount_out: Balance,
         amount_in_max: Balance,
         path: Vec<AccountId>,
+        fees: Vec<u32>,
         to: AccountId,
         deadline: u64,
-    ) -> Result<Vec<Balance>, RouterError> {
-        let factory = self.data().factory;
-        let pair_hash = self.data().pair_code_hash;
-        let factory_ref = factory.as_ref();
-        let pair_hash_ref = pair_hash.as_ref();
-
-        let amounts = get_amounts_in(factory_ref, pair_hash_ref, amount_out, &path)?;
+    ) -> Result<Balance, RouterError> {
+        let amount_in = self._exact_output(amount_out, path, fees, Self::env().caller(), to)?;
         ensure!(
-            amounts[0] <= amount_in_max,
+            amount_in <= amount_in_max,
             RouterError::ExcessiveInputAmount
         );
-        safe_transfer_from(
-            path[0],
-            Self::env().caller(),
-            pair_for(factory_ref, pair_hash_ref, path[0], path[1])?,
-            amounts[0],
-        )?;
-        self._swap(&amounts, path, to)?;
-        Ok(amounts)
+        Ok(amount_in)
     }
 
     #[modifiers(ensure(deadline))]
@@ -277,30 +165,21 @@ impl<T: Storage<data::Data>> Router for T {
         &mut self,
         amount_out_min: Balance,
         path: Vec<AccountId>,
+        fees: Vec<u32>,
         to: AccountId,
         deadline: u64,
-    ) -> Result<Vec<Balance>, RouterError> {
-        let factory = self.data().factory;
-        let pair_hash = self.data().pair_code_hash;
-        let factory_ref = factory.as_ref();
-        let pair_hash_ref = pair_hash.as_ref();
-
-        let received_value = Self::env().transferred_value();
+    ) -> Result<Balance, RouterError> {
         let wnative = self.data().wnative;
-        ensure!(path[0] == wnative, RouterError::InvalidPath);
-        let amounts = get_amounts_out(factory_ref, pair_hash_ref, received_value, &path)?;
+        ensure!(path.first() == Some(&wnative), RouterError::InvalidPath);
+        let received_value = Self::env().transferred_value();
+        wrap(&wnative, received_value)?;
+        let amount_out =
+            self._exact_input(received_value, path, fees, Self::env().account_id(), to)?;
         ensure!(
-            amounts[amounts.len() - 1] >= amount_out_min,
+            amount_out >= amount_out_min,
             RouterError::InsufficientOutputAmount
         );
-        wrap(&wnative, received_value)?;
-        safe_transfer(
-            wnative,
-            pair_for(factory_ref, pair_hash_ref, path[0], path[1])?,
-            amounts[0],
-        )?;
-        self._swap(&amounts, path, to)?;
-        Ok(amounts)
+        Ok(amount_out)
     }
 
     #[modifiers(ensure(deadline))]
@@ -309,241 +188,286 @@ impl<T: Storage<data::Data>> Router for T {
         amount_out: Balance,
         amount_in_max: Balance,
         path: Vec<AccountId>,
+        fees: Vec<u32>,
         to: AccountId,
         deadline: u64,
-    ) -> Result<Vec<Balance>, RouterError> {
-        let factory = self.data().factory;
-        let pair_hash = self.data().pair_code_hash;
-        let factory_ref = factory.as_ref();
-        let pair_hash_ref = pair_hash.as_ref();
-
+    ) -> Result<Balance, RouterError> {
         let wnative = self.data().wnative;
-        ensure!(path[path.len() - 1] == wnative, RouterError::InvalidPath);
-        let amounts = get_amounts_in(factory_ref, pair_hash_ref, amount_out, &path)?;
+        ensure!(path.last() == Some(&wnative), RouterError::InvalidPath);
+        let amount_in = self._exact_output(
+            amount_out,
+            path,
+            fees,
+            Self::env().caller(),
+            Self::env().account_id(),
+        )?;
         ensure!(
-            amounts[0] <= amount_in_max,
+            amount_in <= amount_in_max,
             RouterError::ExcessiveInputAmount
         );
-        safe_transfer_from(
-            path[0],
-            Self::env().caller(),
-            pair_for(factory_ref, pair_hash_ref, path[0], path[1])?,
-            amounts[0],
-        )?;
-        self._swap(&amounts, path, Self::env().account_id())?;
-        unwrap(&wnative, amounts[amounts.len() - 1])?;
-        safe_transfer_native(to, amounts[amounts.len() - 1])?;
-        Ok(amounts)
+        unwrap(&wnative, amount_out)?;
+        safe_transfer_native(to, amount_out)?;
+        Ok(amount_in)
     }
 
     #[modifiers(ensure(deadline))]
-    fn swap_exact_tokens_for_native(
+    default fn swap_exact_tokens_for_native(
         &mut self,
         amount_in: Balance,
         amount_out_min: Balance,
         path: Vec<AccountId>,
+        fees: Vec<u32>,
         to: AccountId,
         deadline: u64,
-    ) -> Result<Vec<Balance>, RouterError> {
-        let factory = self.data().factory;
-        let pair_hash = self.data().pair_code_hash;
-        let factory_ref = factory.as_ref();
-        let pair_hash_ref = pair_hash.as_ref();
-
+    ) -> Result<Balance, RouterError> {
         let wnative = self.data().wnative;
-        ensure!(path[path.len() - 1] == wnative, RouterError::InvalidPath);
-        let amounts = get_amounts_out(factory_ref, pair_hash_ref, amount_in, &path)?;
+        ensure!(path.last() == Some(&wnative), RouterError::InvalidPath);
+        let amount_out = self._exact_input(
+            amount_in,
+            path,
+            fees,
+            Self::env().caller(),
+            Self::env().account_id(),
+        )?;
         ensure!(
-            amounts[amounts.len() - 1] >= amount_out_min,
+            amount_out >= amount_out_min,
             RouterError::InsufficientOutputAmount
         );
-        safe_transfer_from(
-            path[0],
-            Self::env().caller(),
-            pair_for(factory_ref, pair_hash_ref, path[0], path[1])?,
-            amounts[0],
-        )?;
-        self._swap(&amounts, path, Self::env().account_id())?;
-        unwrap(&wnative, amounts[amounts.len() - 1])?;
-        safe_transfer_native(to, amounts[amounts.len() - 1])?;
-        Ok(amounts)
+        unwrap(&wnative, amount_out)?;
+        safe_transfer_native(to, amount_out)?;
+        Ok(amount_out)
     }
 
     #[modifiers(ensure(deadline))]
-    fn swap_native_for_exact_tokens(
+    default fn swap_native_for_exact_tokens(
         &mut self,
         amount_out: Balance,
         path: Vec<AccountId>,
+        fees: Vec<u32>,
         to: AccountId,
         deadline: u64,
-    ) -> Result<Vec<Balance>, RouterError> {
-        let factory = self.data().factory;
-        let pair_hash = self.data().pair_code_hash;
-        let factory_ref = factory.as_ref();
-        let pair_hash_ref = pair_hash.as_ref();
-
+    ) -> Result<Balance, RouterError> {
         let wnative = self.data().wnative;
+        ensure!(path.first() == Some(&wnative), RouterError::InvalidPath);
         let received_value = Self::env().transferred_value();
-
-        ensure!(path[0] == wnative, RouterError::InvalidPath);
-        let amounts = get_amounts_in(factory_ref, pair_hash_ref, amount_out, &path)?;
+        wrap(&wnative, received_value)?;
+        let amount_in = self._exact_output(amount_out, path, fees, Self::env().account_id(), to)?;
         ensure!(
-            amounts[0] <= received_value,
+            amount_in <= received_value,
             RouterError::ExcessiveInputAmount
         );
-        wrap(&wnative, amounts[0])?;
-        safe_transfer(
-            wnative,
-            pair_for(factory_ref, pair_hash_ref, path[0], path[1])?,
-            amounts[0],
-        )?;
-        self._swap(&amounts, path, to)?;
-        if received_value > amounts[0] {
-            safe_transfer_native(Self::env().caller(), received_value - amounts[0])?
+        if received_value > amount_in {
+            unwrap(&wnative, received_value - amount_in)?;
+            safe_transfer_native(Self::env().caller(), received_value - amount_in)?
         }
-        Ok(amounts)
-    }
-
-    default fn quote(
-        &self,
-        amount_a: Balance,
-        reserve_a: Balance,
-        reserve_b: Balance,
-    ) -> Result<Balance, RouterError> {
-        Ok(quote(amount_a, reserve_a, reserve_b)?)
+        Ok(amount_in)
     }
+}
 
-    default fn get_amount_out(
-        &self,
+impl<T: Storage<data::Data>> Internal for T {
+    fn _exact_input(
+        &mut self,
         amount_in: Balance,
-        reserve_in: Balance,
-        reserve_out: Balance,
+        path: Vec<AccountId>,
+        fees: Vec<u32>,
+        payer: AccountId,
+        recipient: AccountId,
     ) -> Result<Balance, RouterError> {
-        Ok(get_amount_out(amount_in, reserve_in, reserve_out)?)
+        check_path(&path, &fees)?;
+        let router = Self::env().account_id();
+        let hops = fees.len();
+        let mut amount = amount_in;
+        for (i, (tokens, fee)) in path.windows(2).zip(fees).enumerate() {
+            amount = self._swap_exact_input(
+                amount,
+                tokens[0],
+                tokens[1],
+                fee,
+                if i == 0 { payer } else { router },
+                if i == hops - 1 { recipient } else { router },
+            )?;
+        }
+        Ok(amount)
     }
 
-    default fn get_amount_in(
-        &self,
+    fn _exact_output(
+        &mut self,
         amount_out: Balance,
-        reserve_in: Balance,
-        reserve_out: Balance,
+        path: Vec<AccountId>,
+        fees: Vec<u32>,
+        payer: AccountId,
+        recipient: AccountId,
     ) -> Result<Balance, RouterError> {
-        Ok(get_amount_in(amount_out, reserve_in, reserve_out)?)
+        check_path(&path, &fees)?;
+        let token_in = path[0];
+        let balance_before = PSP22Ref::balance_of(&token_in, payer);
+        self._swap_exact_output(amount_out, path, fees, payer, recipient)?;
+        Ok(balance_before.saturating_sub(PSP22Ref::balance_of(&token_in, payer)))
     }
 
-    default fn get_amounts_out(
-        &self,
-        amount_in: Balance,
+    fn _swap_exact_output(
+        &mut self,
+        amount_out: Balance,
         path: Vec<AccountId>,
-    ) -> Result<Vec<Balance>, RouterError> {
-        Ok(get_amounts_out(
-            self.data().factory.as_ref(),
-            self.data().pair_code_hash.as_ref(),
-            amount_in,
-            &path,
-        )?)
+        fees: Vec<u32>,
+        payer: AccountId,
+        recipient: AccountId,
+    ) -> Result<Balance, RouterError> {
+        let hops = fees.len();
+        let (token_in, token_out, fee) = (path[hops - 1], path[hops], fees[hops - 1]);
+        let zero_for_one = token_in < token_out;
+        let amount_specified = i128::try_from(amount_out).map_err(|_| PairError::Overflow)?;
+
+        let pool = FactoryRef::get_pool(&self.data().factory, token_in, token_out, fee)
+            .ok_or(RouterError::PairNotFound)?;
+        let callback_data = SwapCallbackData { path, fees, payer };
+        let (amount_0, amount_1) = PoolRef::swap_builder(
+            &pool,
+            recipient,
+            zero_for_one,
+            -amount_specified,
+            sqrt_price_limit(zero_for_one),
+            callback_data.encode(),
+        )
+        .call_flags(CallFlags::default().set_allow_reentry(true))
+        .fire()
+        .map_err(|_| RouterError::PoolCallFailed)??;
+
+        let (amount_in, amount_out_received) = if zero_for_one {
+            (amount_0, amount_1.unsigned_abs())
+        } else {
+            (amount_1, amount_0.unsigned_abs())
+        };
+        // the price limit is never reached unless the pool runs out of liquidity
+        ensure!(
+            amount_out_received == amount_out,
+            RouterError::InsufficientOutputAmount
+        );
+        Ok(amount_in.unsigned_abs())
     }
 
-    default fn get_amounts_in(
-        &self,
-        amount_out: Balance,
-        path: Vec<AccountId>,
-    ) -> Result<Vec<Balance>, RouterError> {
-        Ok(get_amounts_in(
-            self.data().factory.as_ref(),
-            self.data().pair_code_hash.as_ref(),
-            amount_out,
-            &path,
-        )?)
+    fn _swap_exact_input(
+        &mut self,
+        amount_in: Balance,
+        token_in: AccountId,
+        token_out: AccountId,
+        fee: u32,
+        payer: AccountId,
+        recipient: AccountId,
+    ) -> Result<Balance, RouterError> {
+        let zero_for_one = token_in < token_out;
+        let amount_specified = i128::try_from(amount_in).map_err(|_| PairError::Overflow)?;
+
+        let pool = FactoryRef::get_pool(&self.data().factory, token_in, token_out, fee)
+            .ok_or(RouterError::PairNotFound)?;
+        let callback_data = SwapCallbackData {
+            path: vec![token_in, token_out],
+            fees: vec![fee],
+            payer,
+        };
+        let (amount_0, amount_1) = PoolRef::swap_builder(
+            &pool,
+            recipient,
+            zero_for_one,
+            amount_specified,
+            sqrt_price_limit(zero_for_one),
+            callback_data.encode(),
+        )
+        .call_flags(CallFlags::default().set_allow_reentry(true))
+        .fire()
+        .map_err(|_| RouterError::PoolCallFailed)??;
+
+        Ok(if zero_for_one { amount_1 } else { amount_0 }.unsigned_abs())
     }
 }
 
-impl<T: Storage<data::Data>> Internal for T {
-    fn _add_liquidity(
-        &self,
-        token_a: AccountId,
-        token_b: AccountId,
-        amount_a_desired: Balance,
-        amount_b_desired: Balance,
-        amount_a_min: Balance,
-        amount_b_min: Balance,
-    ) -> Result<(Balance, Balance), RouterError> {
-        if FactoryRef::get_pair(&self.data().factory, token_a, token_b).is_none() {
-            FactoryRef::create_pair(&self.data().factory, token_a, token_b)?;
-        };
+impl<T: Storage<data::Data>> SwapCallback for T {
+    default fn swap_callback(
+        &mut self,
+        amount_0_delta: i128,
+        amount_1_delta: i128,
+        data: Vec<u8>,
+    ) -> Result<(), PairError> {
+        // swaps entirely within 0-liquidity regions are not supported
+        ensure!(
+            amount_0_delta > 0 || amount_1_delta > 0,
+            PairError::InsufficientInputAmount
+        );
+        let decoded =
+            SwapCallbackData::decode(&mut &data[..]).map_err(|_| PairError::InvalidCallbackData)?;
+        let hops = decoded.fees.len();
+        ensure!(
+            hops > 0 && decoded.path.len() == hops + 1,
+            PairError::InvalidCallbackData
+        );
+        let (token_in, token_out, fee) = (
+            decoded.path[hops - 1],
+            decoded.path[hops],
+            decoded.fees[hops - 1],
+        );
+        let pool = Self::env().caller();
+        ensure!(
+            FactoryRef::get_pool(&self.data().factory, token_in, token_out, fee) == Some(pool),
+            PairError::InvalidCallbackCaller
+        );
 
-        let (reserve_a, reserve_b) = get_reserves(
-            self.data().factory.as_ref(),
-            self.data().pair_code_hash.as_ref(),
-            token_a,
-            token_b,
-        )?;
-        if reserve_a == 0 && reserve_b == 0 {
-            return Ok((amount_a_desired, amount_b_desired))
+        let amount_to_pay = if amount_0_delta > 0 {
+            amount_0_delta
+        } else {
+            amount_1_delta
         }
-
-        let amount_b_optimal = quote(amount_a_desired, reserve_a, reserve_b)?;
-        if amount_b_optimal <= amount_b_desired {
-            ensure!(
-                amount_b_optimal >= amount_b_min,
-                RouterError::InsufficientBAmount
-            );
-            Ok((amount_a_desired, amount_b_optimal))
+        .unsigned_abs();
+        if hops > 1 {
+            // exact output: pay this hop by swapping the previous one for exactly the amount owed
+            let (mut path, mut fees) = (decoded.path, decoded.fees);
+            path.pop();
+            fees.pop();
+            self._swap_exact_output(amount_to_pay, path, fees, decoded.payer, pool)
+                .map_err(|error| match error {
+                    RouterError::PairError(error) => error,
+                    // the previous hop could not be swapped, so this one is not paid
+                    _ => PairError::InsufficientInputAmount,
+                })?;
+        } else if decoded.payer == Self::env().account_id() {
+            safe_transfer(token_in, pool, amount_to_pay)?;
         } else {
-            let amount_a_optimal = quote(amount_b_desired, reserve_b, reserve_a)?;
-            // amount_a_optimal <= amount_a_desired holds as amount_b_optimal > amount_b_desired
-            ensure!(
-                amount_a_optimal >= amount_a_min,
-                RouterError::InsufficientAAmount
-            );
-            Ok((amount_a_optimal, amount_b_desired))
+            safe_transfer_from(token_in, decoded.payer, pool, amount_to_pay)?;
         }
+        Ok(())
     }
+}
 
-    fn _swap(
-        &self,
-        amounts: &Vec<Balance>,
-        path: Vec<AccountId>,
-        _to: AccountId,
-    ) -> Result<(), RouterError> {
-        let factory_ref = self.data().factory.as_ref();
-        let pair_hash = self.data().pair_code_hash.as_ref();
+/// Checks that `fees` holds the fee of each hop of a path of at least two tokens.
+fn check_path(path: &[AccountId], fees: &[u32]) -> Result<(), RouterError> {
+    ensure!(
+        path.len() >= 2 && fees.len() == path.len() - 1,
+        RouterError::InvalidPath
+    );
+    Ok(())
+}
 
-        for i in 0..path.len() - 1 {
-            let (input, output) = (path[i], path[i + 1]);
-            let (token_0, _) = sort_tokens(input, output)?;
-            let amount_out = amounts[i + 1];
-            let (amount_0_out, amount_1_out) = if input == token_0 {
-                (0, amount_out)
-            } else {
-                (amount_out, 0)
-            };
-            let to = if i < path.len() - 2 {
-                pair_for(factory_ref, pair_hash, output, path[i + 2])?
-            } else {
-                _to
-            };
-            PoolRef::swap_builder(
-                &pair_for(factory_ref, pair_hash, input, output)?,
-                amount_0_out,
-                amount_1_out,
-                to,
-            )
-            .call_flags(CallFlags::default().set_allow_reentry(true))
-            .fire()
-            .unwrap()?
-        }
-        Ok(())
+/// The most extreme price a swap may reach, i.e. swaps are not limited by price.
+fn sqrt_price_limit(zero_for_one: bool) -> WrappedU256 {
+    if zero_for_one {
+        MIN_SQRT_RATIO + U256::one()
+    } else {
+        MAX_SQRT_RATIO - U256::one()
     }
+    .into()
 }
 
+/// Rejects the call once the block timestamp is past `deadline`.
+///
+/// Independent of the storage of `T`, so that any contract can reuse it for its own deadlines.
 #[modifier_definition]
 pub fn ensure<T, F, R, E>(instance: &mut T, body: F, deadline: u64) -> Result<R, E>
 where
-    T: Storage<data::Data>,
     F: FnOnce(&mut T) -> Result<R, E>,
     E: From<RouterError>,
 {
-    ensure!(deadline >= T::env().block_timestamp(), RouterError::Expired);
+    ensure!(
+        deadline >= ink_env::block_timestamp::<DefaultEnvironment>(),
+        RouterError::Expired
+    );
     body(instance)
 }
@@ -0,0 +1,59 @@
+use super::pair::PairError;
+use ink_prelude::vec::Vec;
+use openbrush::traits::Balance;
+
+#[openbrush::wrapper]
+pub type MintCallbackRef = dyn MintCallback;
+
+/// Any contract that calls `Pair::mint` must implement this trait.
+#[openbrush::trait_definition]
+pub trait MintCallback {
+    /// Called on the caller after minting liquidity to a position.
+    ///
+    /// The implementation must pay the pool tokens owed for the minted liquidity,
+    /// `data` is passed through unchanged from the `Pair::mint` call.
+    #[ink(message)]
+    fn mint_callback(
+        &mut self,
+        amount_0_owed: Balance,
+        amount_1_owed: Balance,
+        data: Vec<u8>,
+    ) -> Result<(), PairError>;
+}
+
+#[openbrush::wrapper]
+pub type SwapCallbackRef = dyn SwapCallback;
+
+/// Any contract that calls `Pair::swap` must implement this trait.
+#[openbrush::trait_definition]
+pub trait SwapCallback {
+    /// Called on the caller after executing a swap.
+    ///
+    /// `amount_0_delta`/`amount_1_delta` are the amounts of token0/token1 that were sent (negative)
+    /// or must be received (positive) by the pool. The implementation must pay the positive one.
+    #[ink(message)]
+    fn swap_callback(
+        &mut self,
+        amount_0_delta: i128,
+        amount_1_delta: i128,
+        data: Vec<u8>,
+    ) -> Result<(), PairError>;
+}
+
+#[openbrush::wrapper]
+pub type FlashCallbackRef = dyn FlashCallback;
+
+/// Any contract that calls `Pair::flash` must implement this trait.
+#[openbrush::trait_definition]
+pub trait FlashCallback {
+    /// Called on the caller after transferring the flashed tokens to the recipient.
+    ///
+    /// The implementation must repay the pool the flashed amounts plus `fee_0`/`fee_1`.
+    #[ink(message)]
+    fn flash_callback(
+        &mut self,
+        fee_0: Balance,
+        fee_1: Balance,
+        data: Vec<u8>,
+    ) -> Result<(), PairError>;
+}
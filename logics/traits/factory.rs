@@ -1,49 +1,105 @@
 use crate::traits::pair::PairError;
 use ink_env::Hash;
-use openbrush::traits::AccountId;
+use ink_prelude::vec::Vec;
+use openbrush::{
+    contracts::traits::ownable::*,
+    traits::AccountId,
+};
 
 #[openbrush::wrapper]
 pub type FactoryRef = dyn Factory;
 
 #[openbrush::trait_definition]
 pub trait Factory {
-		#[ink(message)]
-		fn owner(&self) -> u8;
+    /// The current owner of the factory.
+    #[ink(message)]
+    fn owner(&self) -> AccountId;
 		
-		#[ink(message)]
-		fn fee_amount_tickspacing(&self,fee: u8) -> u8;
+    /// Returns the tick spacing for a given fee amount if enabled, or 0 if not enabled.
+    ///
+    /// The fee is in hundredths of a bip, i.e. 1e-6.
+    #[ink(message)]
+    fn fee_amount_tick_spacing(&self, fee: u32) -> i32;
 
-		
-		#[ink(message)]
-		fn get_pool(&self, token_a: AccountId, token_b: AccountId, fee:u8) -> Option<AccountId>;
-		
+    /// Returns the pool address for a given pair of tokens and a fee, or `None` if it does not exist.
+    #[ink(message)]
+    fn get_pool(&self, token_a: AccountId, token_b: AccountId, fee: u32) -> Option<AccountId>;
+
+    /// Returns the pool created at the given index, in order of creation.
+    #[ink(message)]
+    fn all_pools(&self, index: u64) -> Option<AccountId>;
+
+    /// Returns the number of pools created by the factory.
+    #[ink(message)]
+    fn all_pools_length(&self) -> u64;
+
+    /// Returns up to `count` pools starting at the index `start`, in order of creation.
+    #[ink(message)]
+    fn pools_range(&self, start: u64, count: u64) -> Vec<AccountId>;
+
+    /// Returns up to `count` pools that have the given token as token0 or token1, starting at the
+    /// index `start`, in order of creation.
+    #[ink(message)]
+    fn pools_for_token(&self, token: AccountId, start: u64, count: u64) -> Vec<AccountId>;
+
+    /// Returns the number of pools that have the given token as token0 or token1.
+    #[ink(message)]
+    fn pools_for_token_length(&self, token: AccountId) -> u64;
+
+    /// Creates a pool for the given two tokens and fee.
+    ///
+    /// The tick spacing is retrieved from the fee, the call fails if the fee is not enabled.
     #[ink(message)]
     fn create_pool(
-			&mut self,
-			token_a: AccountId,
-			token_b: AccountId,
-			fee:u8
-			tick_spacing: i8,
-			pool_contract: AccountId
+        &mut self,
+        token_a: AccountId,
+        token_b: AccountId,
+        fee: u32,
     ) -> Result<AccountId, FactoryError>;
-		
-		#[ink(message)]
-		fn set_owner(&self, _owner: AccountId) -> AccountId;
-		
-		#[ink(message)]
-		fn enable_fee_amount(mut &self, fee:u8, tickspacing:u8) -> AccountId;
 
+    /// The code hash new pools are instantiated from.
+    #[ink(message)]
+    fn pool_code_hash(&self) -> Hash;
+
+    /// Sets the code hash used for pools created from now on, existing pools are not affected.
+    ///
+    /// Only callable by the owner.
+    #[ink(message)]
+    fn set_pool_code_hash(&mut self, pool_code_hash: Hash) -> Result<(), FactoryError>;
+
+    /// Replaces the code of an existing pool, keeping its storage and balances.
+    ///
+    /// The new code must keep the pool's storage layout compatible. Only callable by the owner.
+    #[ink(message)]
+    fn migrate_pool(&mut self, pool: AccountId, new_code_hash: Hash) -> Result<(), FactoryError>;
+
+    /// Updates the owner of the factory, emitting `OwnerChanged` through
+    /// `ownable::Internal::_emit_ownership_transferred_event`.
+    ///
+    /// Only callable by the current owner, the new owner can't be the zero address.
+    #[ink(message)]
+    fn set_owner(&mut self, owner: AccountId) -> Result<(), FactoryError>;
+
+    /// Enables a fee amount with the given tick spacing.
+    ///
+    /// Fee amounts may never be removed once enabled. The tick spacing must be positive and is capped
+    /// at 16384 so that `tick_bitmap::next_initialized_tick_within_one_word` cannot overflow an i32
+    /// from a valid tick. Only callable by the owner.
+    #[ink(message)]
+    fn enable_fee_amount(&mut self, fee: u32, tick_spacing: i32) -> Result<(), FactoryError>;
 }
 
 #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
 #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
 pub enum FactoryError {
     PairError(PairError),
-    CallerIsNotFeeSetter,
+    OwnableError(OwnableError),
     ZeroAddress,
     IdenticalAddresses,
     PairExists,
-		TickSpacingIsZero,
+    InvalidTickSpacing,
+    FeeAmountAlreadyEnabled,
+    PairInstantiationFailed,
 }
 
 impl From<PairError> for FactoryError {
@@ -51,3 +107,9 @@ impl From<PairError> for FactoryError {
         FactoryError::PairError(error)
     }
 }
+
+impl From<OwnableError> for FactoryError {
+    fn from(error: OwnableError) -> Self {
+        FactoryError::OwnableError(error)
+    }
+}
@@ -1,5 +1,7 @@
+pub mod callback;
 pub mod factory;
 pub mod pair;
+pub mod position_manager;
 pub mod router;
 pub mod types;
 pub mod wnative;
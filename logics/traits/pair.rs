@@ -1,57 +1,186 @@
+use ink_prelude::vec::Vec;
 use openbrush::{
-    contracts::{
-        reentrancy_guard::*,
-        traits::{
-            ownable::*,
-            pausable::*,
-            psp22::PSP22Error,
-        },
+    contracts::traits::{
+        ownable::*,
+        pausable::*,
+        psp22::PSP22Error,
     },
     traits::{
         AccountId,
         Balance,
-        Timestamp,
+        Hash,
     },
 };
 
 use super::types::WrappedU256;
+use crate::helpers::math::MathError;
+use ink_primitives::KeyPtr;
+use ink_storage::traits::{
+    forward_allocate_packed,
+    forward_clear_packed,
+    forward_pull_packed,
+    forward_push_packed,
+    PackedLayout,
+    SpreadAllocate,
+    SpreadLayout,
+};
+
+#[cfg(feature = "std")]
+use ink_metadata::layout::{
+    CellLayout,
+    Layout,
+    LayoutKey,
+};
+#[cfg(feature = "std")]
+use ink_storage::traits::StorageLayout;
 
 #[openbrush::wrapper]
 pub type PoolRef = dyn Pair;
 
 #[openbrush::trait_definition]
 pub trait Pair {
+    /// The live state of the pool: price, tick, oracle indices, protocol fee and lock.
     #[ink(message)]
-    fn get_reserves(&self) -> (Balance, Balance, Timestamp);
+    fn slot0(&self) -> Slot0;
 
+    /// Records the immutable parameters of the pool, called once by the factory right after deployment.
+    ///
+    /// Fails if the pool is already set up or `tick_spacing` is not positive.
     #[ink(message)]
-    fn price_0_cumulative_last(&self) -> WrappedU256;
+    fn setup(
+        &mut self,
+        token_0: AccountId,
+        token_1: AccountId,
+        fee: u32,
+        tick_spacing: i32,
+    ) -> Result<(), PairError>;
 
+    /// Sets the initial price for the pool.
+    ///
+    /// Can be called only once after `setup`, the price is a sqrt(amount_token_1/amount_token_0) Q64.96 value.
     #[ink(message)]
-    fn price_1_cumulative_last(&self) -> WrappedU256;
+    fn initialize(&mut self, sqrt_price_x96: WrappedU256) -> Result<(), PairError>;
 
+    /// Adds liquidity for the given recipient/tick_lower/tick_upper position.
+    ///
+    /// The caller receives a `MintCallback::mint_callback` in which it must pay
+    /// the returned token0/token1 amounts owed for the liquidity.
     #[ink(message)]
-    fn initialize(&mut self, token_0: AccountId, token_1: AccountId) -> Result<(), PairError>;
+    fn mint(
+        &mut self,
+        recipient: AccountId,
+        tick_lower: i32,
+        tick_upper: i32,
+        amount: u128,
+        data: Vec<u8>,
+    ) -> Result<(Balance, Balance), PairError>;
 
+    /// Collects tokens owed to a position of the caller.
+    ///
+    /// Does not recompute fees earned, which must be done either via mint or burn of any amount
+    /// of liquidity. Returns the amounts actually transferred, at most the requested ones.
     #[ink(message)]
-    fn mint(&mut self, to: AccountId) -> Result<Balance, PairError>;
+    fn collect(
+        &mut self,
+        recipient: AccountId,
+        tick_lower: i32,
+        tick_upper: i32,
+        amount_0_requested: Balance,
+        amount_1_requested: Balance,
+    ) -> Result<(Balance, Balance), PairError>;
 
+    /// Burns liquidity from the caller's position and accounts the tokens owed for it to the position.
+    ///
+    /// Can be used to trigger a recalculation of fees owed to a position by calling with an amount of 0.
+    /// Tokens must be withdrawn via `collect`.
     #[ink(message)]
-    fn burn(&mut self, to: AccountId) -> Result<(Balance, Balance), PairError>;
+    fn burn(
+        &mut self,
+        tick_lower: i32,
+        tick_upper: i32,
+        amount: u128,
+    ) -> Result<(Balance, Balance), PairError>;
 
+    /// Swaps token0 for token1, or token1 for token0.
+    ///
+    /// A positive `amount_specified` is an exact input, a negative one an exact output.
+    /// The swap stops early once the price reaches `sqrt_price_limit_x96`. The caller
+    /// receives a `SwapCallback::swap_callback` in which it must pay the input amount.
+    /// Returns the signed deltas of the pool's token0/token1 balances.
     #[ink(message)]
     fn swap(
         &mut self,
-        amount_0_out: Balance,
-        amount_1_out: Balance,
-        to: AccountId,
+        recipient: AccountId,
+        zero_for_one: bool,
+        amount_specified: i128,
+        sqrt_price_limit_x96: WrappedU256,
+        data: Vec<u8>,
+    ) -> Result<(i128, i128), PairError>;
+
+    /// Receives token0 and/or token1 and pays them back, plus a fee, in the callback.
+    ///
+    /// The caller receives a `FlashCallback::flash_callback` in which it must repay the borrowed
+    /// amounts plus the fees. Anything paid on top of the borrowed amounts is credited to in-range LPs.
+    #[ink(message)]
+    fn flash(
+        &mut self,
+        recipient: AccountId,
+        amount_0: Balance,
+        amount_1: Balance,
+        data: Vec<u8>,
+    ) -> Result<(), PairError>;
+
+    /// Increases the maximum number of price and liquidity observations that this pool will store.
+    ///
+    /// This method is no-op if the pool already has an observation cardinality next greater than
+    /// or equal to the input one.
+    #[ink(message)]
+    fn increase_observation_cardinality_next(
+        &mut self,
+        observation_cardinality_next: u16,
     ) -> Result<(), PairError>;
 
+    /// Returns the cumulative tick and liquidity as of each timestamp `seconds_ago` from the current block timestamp.
+    ///
+    /// To get a time weighted average tick or liquidity-in-range, call this with two values, one representing
+    /// the beginning of the period and another for the end of the period. Fails with `OracleTargetTooOld`
+    /// if any of the requested points predates the oldest stored observation.
     #[ink(message)]
-    fn skim(&mut self, to: AccountId) -> Result<(), PairError>;
+    fn observe(&self, seconds_agos: Vec<u32>) -> Result<(Vec<i64>, Vec<WrappedU256>), PairError>;
 
+    /// Returns a snapshot of the tick cumulative, seconds per liquidity and seconds inside a tick range.
+    ///
+    /// Snapshots must only be compared to other snapshots, taken over a period for which a position existed.
+    /// I.e., snapshots cannot be compared if a position is not held for the entire period between when the
+    /// first snapshot is taken and the second snapshot is taken. Fails if either tick is not initialized.
     #[ink(message)]
-    fn sync(&mut self) -> Result<(), PairError>;
+    fn snapshot_cumulatives_inside(
+        &self,
+        tick_lower: i32,
+        tick_upper: i32,
+    ) -> Result<(i64, WrappedU256, u64), PairError>;
+
+    /// Sets the denominator of the protocol's % share of the fees.
+    ///
+    /// Each value must be 0 (off) or between 4 and 10. Only callable by the factory owner.
+    #[ink(message)]
+    fn set_fee_protocol(&mut self, fee_protocol_0: u8, fee_protocol_1: u8)
+        -> Result<(), PairError>;
+
+    /// Collects the protocol fees accrued to the pool, up to the requested amounts.
+    ///
+    /// Only callable by the factory owner.
+    #[ink(message)]
+    fn collect_protocol(
+        &mut self,
+        recipient: AccountId,
+        amount_0_requested: Balance,
+        amount_1_requested: Balance,
+    ) -> Result<(Balance, Balance), PairError>;
+
+    /// The amounts of token0 and token1 that are owed to the protocol.
+    #[ink(message)]
+    fn protocol_fees(&self) -> (Balance, Balance);
 
     #[ink(message)]
     fn get_token_0(&self) -> AccountId;
@@ -59,32 +188,125 @@ pub trait Pair {
     #[ink(message)]
     fn get_token_1(&self) -> AccountId;
 
-		#[ink(message)]
-		fn factory(&self) -> AccountId;
+    /// The contract that deployed the pool.
+    #[ink(message)]
+    fn factory(&self) -> AccountId;
+
+    /// The pool's fee in hundredths of a bip, i.e. 1e-6.
+    #[ink(message)]
+    fn fee(&self) -> u32;
 
-		#[ink(message)]
-		fn token0(&self) -> AccountId;
-		
-		#[ink(message)]
-		fn token_1(&self) -> AccountId;
-		
-		#[ink(message)]
-		fn fee(&self) -> u8;
+    #[ink(message)]
+    fn tick_spacing(&self) -> i32;
 
-		#[ink(message)]
-		fn tick_spacing(&self) -> i8;
+    #[ink(message)]
+    fn max_liquidity_per_tick(&self) -> u128;
+
+    /// Returns the info about a position by the position's key.
+    #[ink(message)]
+    fn positions(&self, owner: AccountId, tick_lower: i32, tick_upper: i32) -> Position;
 
-		#[ink(message)]
-		fn max_liquidity_per_tick(&self) -> u8;
+    /// Replaces the code of the pool with the given code hash and bumps the pool's version.
+    ///
+    /// Only callable by the factory that deployed the pool.
+    #[ink(message)]
+    fn set_code_hash(&mut self, code_hash: Hash) -> Result<(), PairError>;
+
+    /// The number of times the pool's code has been migrated.
+    #[ink(message)]
+    fn version(&self) -> u32;
+}
+
+/// The live state of the pool, read and written together as a single storage cell.
+#[derive(
+    Default, Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode, PackedLayout,
+)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct Slot0 {
+    /// The current price of the pool as a sqrt(token1/token0) Q64.96 value.
+    pub sqrt_price_x96: WrappedU256,
+    /// The current tick of the pool, i.e. according to the last tick transition that was run.
+    pub tick: i32,
+    /// The index of the last oracle observation that was written.
+    pub observation_index: u16,
+    /// The current maximum number of observations stored in the pool.
+    pub observation_cardinality: u16,
+    /// The next maximum number of observations, to be updated when the observation is written.
+    pub observation_cardinality_next: u16,
+    /// The current protocol fee as a percentage of the swap fee taken on withdrawal,
+    /// represented as an integer denominator (1/x)%, token0 in the low and token1 in the high 4 bits.
+    pub fee_protocol: u8,
+    /// Whether the pool is currently unlocked, i.e. initialized and not being reentered.
+    pub unlocked: bool,
+}
+
+impl SpreadLayout for Slot0 {
+    const FOOTPRINT: u64 = 1;
+    const REQUIRES_DEEP_CLEAN_UP: bool = false;
+
+    fn pull_spread(ptr: &mut KeyPtr) -> Self {
+        forward_pull_packed::<Self>(ptr)
+    }
+
+    fn push_spread(&self, ptr: &mut KeyPtr) {
+        forward_push_packed::<Self>(self, ptr)
+    }
+
+    fn clear_spread(&self, ptr: &mut KeyPtr) {
+        forward_clear_packed::<Self>(self, ptr)
+    }
+}
+
+impl SpreadAllocate for Slot0 {
+    fn allocate_spread(ptr: &mut KeyPtr) -> Self {
+        forward_allocate_packed::<Self>(ptr)
+    }
+}
+
+#[cfg(feature = "std")]
+impl StorageLayout for Slot0 {
+    fn layout(key_ptr: &mut KeyPtr) -> Layout {
+        Layout::Cell(CellLayout::new::<Self>(LayoutKey::from(
+            key_ptr.advance_by(1),
+        )))
+    }
+}
+
+/// Info stored for each user's position.
+///
+/// Please refer https://github.com/Uniswap/v3-core/blob/main/contracts/libraries/Position.sol
+#[derive(
+    Default,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    scale::Encode,
+    scale::Decode,
+    SpreadLayout,
+    PackedLayout,
+    SpreadAllocate,
+)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+pub struct Position {
+    /// The amount of liquidity owned by this position.
+    pub liquidity: u128,
+    /// Fee growth per unit of liquidity as of the last update to liquidity or fees owed.
+    pub fee_growth_inside_0_last_x128: WrappedU256,
+    pub fee_growth_inside_1_last_x128: WrappedU256,
+    /// The fees owed to the position owner in token0/token1.
+    pub tokens_owed_0: Balance,
+    pub tokens_owed_1: Balance,
 }
 
 #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
 #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
 pub enum PairError {
     PSP22Error(PSP22Error),
+    MathError(MathError),
     OwnableError(OwnableError),
     PausableError(PausableError),
-    ReentrancyGuardError(ReentrancyGuardError),
     K,
     InsufficientLiquidityMinted,
     InsufficientLiquidityBurned,
@@ -131,6 +353,27 @@ pub enum PairError {
     AddOverflow1,
     CastOverflow1,
     CastOverflow2,
+    TickNotSpaced,
+    MaxLiquidityPerTick,
+    NoPositionLiquidity,
+    TickLowerNotLessThanUpper,
+    TickLowerTooLow,
+    TickUpperTooHigh,
+    ZeroAmountSpecified,
+    InvalidSqrtPriceLimit,
+    InvalidCallbackData,
+    InvalidCallbackCaller,
+    FlashNotRepaid,
+    OracleNotInitialized,
+    OracleTargetTooOld,
+    TickNotInitialized,
+    InvalidFeeProtocol,
+    CallerIsNotFactoryOwner,
+    AlreadyInitialized,
+    AlreadySetUp,
+    NotSetUp,
+    InvalidTickSpacing,
+    SetCodeHashFailed,
 }
 
 impl From<OwnableError> for PairError {
@@ -145,14 +388,14 @@ impl From<PausableError> for PairError {
     }
 }
 
-impl From<PSP22Error> for PairError {
-    fn from(error: PSP22Error) -> Self {
-        PairError::PSP22Error(error)
+impl From<MathError> for PairError {
+    fn from(error: MathError) -> Self {
+        PairError::MathError(error)
     }
 }
 
-impl From<ReentrancyGuardError> for PairError {
-    fn from(error: ReentrancyGuardError) -> Self {
-        PairError::ReentrancyGuardError(error)
+impl From<PSP22Error> for PairError {
+    fn from(error: PSP22Error) -> Self {
+        PairError::PSP22Error(error)
     }
 }
@@ -0,0 +1,205 @@
+use super::{
+    factory::FactoryError,
+    pair::PairError,
+    router::RouterError,
+    types::WrappedU256,
+};
+use crate::helpers::math::MathError;
+use ink_prelude::string::String;
+use ink_storage::traits::{
+    PackedLayout,
+    SpreadAllocate,
+    SpreadLayout,
+};
+use openbrush::{
+    contracts::{
+        psp22::PSP22Error,
+        psp34::{
+            Id,
+            PSP34Error,
+        },
+    },
+    traits::{
+        AccountId,
+        Balance,
+    },
+};
+
+#[cfg(feature = "std")]
+use ink_storage::traits::StorageLayout;
+
+#[openbrush::wrapper]
+pub type PositionManagerRef = dyn PositionManager;
+
+/// Wraps positions in pools as PSP34 tokens, which can be held and transferred like any NFT.
+///
+/// Except for `mint`, the messages are only callable by the owner of the token or an approved operator.
+#[openbrush::trait_definition]
+pub trait PositionManager {
+    /// The factory the pools of all positions are looked up in.
+    #[ink(message)]
+    fn factory(&self) -> AccountId;
+
+    /// Returns the position associated with a given token id, or `None` if the token does not exist.
+    #[ink(message)]
+    fn positions(&self, token_id: Id) -> Option<Position>;
+
+    /// Creates a new position wrapped in a NFT minted to `params.recipient`.
+    ///
+    /// The pool is created through the factory and initialized at `params.sqrt_price_x96` if it
+    /// does not exist yet. The caller must have approved the manager to spend both tokens.
+    /// Returns the token id, the liquidity added and the amounts of token0/token1 paid.
+    #[ink(message)]
+    fn mint(
+        &mut self,
+        params: MintParams,
+    ) -> Result<(Id, u128, Balance, Balance), PositionManagerError>;
+
+    /// Increases the amount of liquidity in a position, with tokens paid by the caller.
+    ///
+    /// Returns the liquidity added and the amounts of token0/token1 paid.
+    #[ink(message)]
+    fn increase_liquidity(
+        &mut self,
+        token_id: Id,
+        amount_0_desired: Balance,
+        amount_1_desired: Balance,
+        amount_0_min: Balance,
+        amount_1_min: Balance,
+        deadline: u64,
+    ) -> Result<(u128, Balance, Balance), PositionManagerError>;
+
+    /// Decreases the amount of liquidity in a position and accounts it to the position.
+    ///
+    /// The released tokens are not transferred, they must be withdrawn via `collect`.
+    /// Returns the amounts of token0/token1 accounted to the position.
+    #[ink(message)]
+    fn decrease_liquidity(
+        &mut self,
+        token_id: Id,
+        liquidity: u128,
+        amount_0_min: Balance,
+        amount_1_min: Balance,
+        deadline: u64,
+    ) -> Result<(Balance, Balance), PositionManagerError>;
+
+    /// Collects up to a maximum amount of fees and released tokens owed to a position to the recipient.
+    ///
+    /// Returns the amounts of token0/token1 transferred.
+    #[ink(message)]
+    fn collect(
+        &mut self,
+        token_id: Id,
+        recipient: AccountId,
+        amount_0_max: Balance,
+        amount_1_max: Balance,
+    ) -> Result<(Balance, Balance), PositionManagerError>;
+
+    /// Burns a token id, which deletes it from the manager.
+    ///
+    /// The position must have 0 liquidity and all tokens must be collected first.
+    #[ink(message)]
+    fn burn(&mut self, token_id: Id) -> Result<(), PositionManagerError>;
+
+    /// Returns the metadata of a token id as a base64 encoded JSON data URI.
+    ///
+    /// The metadata describes the pair symbols, fee tier, price range and whether the position is
+    /// in range, with an SVG image embedded as a data URI as well.
+    #[ink(message)]
+    fn token_uri(&self, token_id: Id) -> Result<String, PositionManagerError>;
+}
+
+/// Parameters of `PositionManager::mint`.
+#[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct MintParams {
+    /// Must be sorted, i.e. `token_0 < token_1`.
+    pub token_0: AccountId,
+    pub token_1: AccountId,
+    pub fee: u32,
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+    pub amount_0_desired: Balance,
+    pub amount_1_desired: Balance,
+    pub amount_0_min: Balance,
+    pub amount_1_min: Balance,
+    pub recipient: AccountId,
+    /// The price the pool is initialized at if it does not exist yet, ignored otherwise.
+    pub sqrt_price_x96: WrappedU256,
+}
+
+/// Details of the liquidity held in a pool on behalf of a position token.
+///
+/// Please refer https://github.com/Uniswap/v3-periphery/blob/main/contracts/NonfungiblePositionManager.sol
+#[derive(
+    Default,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    scale::Encode,
+    scale::Decode,
+    SpreadLayout,
+    PackedLayout,
+    SpreadAllocate,
+)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+pub struct Position {
+    pub token_0: AccountId,
+    pub token_1: AccountId,
+    /// The fee of the pool in hundredths of a bip, i.e. 1e-6.
+    pub fee: u32,
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+    /// The liquidity of the position in the pool.
+    pub liquidity: u128,
+    /// The fee growth of the aggregate position as of the last action on the individual position.
+    pub fee_growth_inside_0_last_x128: WrappedU256,
+    pub fee_growth_inside_1_last_x128: WrappedU256,
+    /// How many uncollected tokens are owed to the position, as of the last computation.
+    pub tokens_owed_0: Balance,
+    pub tokens_owed_1: Balance,
+}
+
+#[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum PositionManagerError {
+    PSP22Error(PSP22Error),
+    PSP34Error(PSP34Error),
+    FactoryError(FactoryError),
+    PairError(PairError),
+    MathError(MathError),
+    RouterError(RouterError),
+    TokensNotSorted,
+    PoolNotFound,
+    PriceSlippageCheck,
+    TokenNotFound,
+    NotApproved,
+    ZeroLiquidity,
+    InsufficientLiquidity,
+    NothingToCollect,
+    NotCleared,
+    PoolCallFailed,
+}
+
+macro_rules! impl_froms {
+    ( $( $error:ident ),* ) => {
+        $(
+            impl From<$error> for PositionManagerError {
+                fn from(error: $error) -> Self {
+                    PositionManagerError::$error(error)
+                }
+            }
+        )*
+    };
+}
+
+impl_froms!(
+    PSP22Error,
+    PSP34Error,
+    FactoryError,
+    PairError,
+    MathError,
+    RouterError
+);
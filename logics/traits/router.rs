@@ -18,6 +18,11 @@ use openbrush::{
 #[openbrush::wrapper]
 pub type RouterRef = dyn Router;
 
+/// Pools are identified by their tokens and fee, swap paths carry the fee of each hop in `fees`,
+/// i.e. `fees[i]` is the fee of the pool between `path[i]` and `path[i + 1]`.
+///
+/// Swaps are paid from the `SwapCallback` implemented by the router, the caller must have
+/// approved the router to spend the input token.
 #[openbrush::trait_definition]
 pub trait Router {
     #[ink(message)]
@@ -26,148 +31,81 @@ pub trait Router {
     #[ink(message)]
     fn wnative(&self) -> AccountId;
 
-    #[ink(message)]
-    fn add_liquidity(
-        &mut self,
-        token_a: AccountId,
-        token_b: AccountId,
-        amount_a_desired: Balance,
-        amount_b_desired: Balance,
-        amount_a_min: Balance,
-        amount_b_min: Balance,
-        to: AccountId,
-        deadline: u64,
-    ) -> Result<(Balance, Balance, Balance), RouterError>;
-
-    #[ink(message)]
-    fn remove_liquidity(
-        &mut self,
-        token_a: AccountId,
-        token_b: AccountId,
-        liquidity: Balance,
-        amount_a_min: Balance,
-        amount_b_min: Balance,
-        to: AccountId,
-        deadline: u64,
-    ) -> Result<(Balance, Balance), RouterError>;
-
-    #[ink(message, payable)]
-    fn add_liquidity_native(
-        &mut self,
-        token: AccountId,
-        amount_token_desired: Balance,
-        amount_token_min: Balance,
-        amount_native_min: Balance,
-        to: AccountId,
-        deadline: u64,
-    ) -> Result<(Balance, Balance, Balance), RouterError>;
-
-    #[ink(message)]
-    fn remove_liquidity_native(
-        &mut self,
-        token: AccountId,
-        liquidity: Balance,
-        amount_token_min: Balance,
-        amount_native_min: Balance,
-        to: AccountId,
-        deadline: u64,
-    ) -> Result<(Balance, Balance), RouterError>;
-
+    /// Swaps exactly `amount_in` of `path[0]` for as much as possible of the last token of the path.
+    ///
+    /// Returns the amount of the last token received by `to`.
     #[ink(message)]
     fn swap_exact_tokens_for_tokens(
         &mut self,
         amount_in: Balance,
         amount_out_min: Balance,
         path: Vec<AccountId>,
+        fees: Vec<u32>,
         to: AccountId,
         deadline: u64,
-    ) -> Result<Vec<Balance>, RouterError>;
+    ) -> Result<Balance, RouterError>;
 
+    /// Swaps as little as possible of `path[0]` for exactly `amount_out` of the last token of the path.
+    ///
+    /// Returns the amount of `path[0]` paid.
     #[ink(message)]
     fn swap_tokens_for_exact_tokens(
         &mut self,
         amount_out: Balance,
         amount_in_max: Balance,
         path: Vec<AccountId>,
+        fees: Vec<u32>,
         to: AccountId,
         deadline: u64,
-    ) -> Result<Vec<Balance>, RouterError>;
+    ) -> Result<Balance, RouterError>;
 
+    /// Like `swap_exact_tokens_for_tokens`, for the transferred value wrapped, `path[0]` must be wnative.
     #[ink(message, payable)]
     fn swap_exact_native_for_tokens(
         &mut self,
         amount_out_min: Balance,
         path: Vec<AccountId>,
+        fees: Vec<u32>,
         to: AccountId,
         deadline: u64,
-    ) -> Result<Vec<Balance>, RouterError>;
+    ) -> Result<Balance, RouterError>;
 
+    /// Like `swap_tokens_for_exact_tokens`, unwrapping the output, the last token of the path must be wnative.
     #[ink(message)]
     fn swap_tokens_for_exact_native(
         &mut self,
         amount_out: Balance,
         amount_in_max: Balance,
         path: Vec<AccountId>,
+        fees: Vec<u32>,
         to: AccountId,
         deadline: u64,
-    ) -> Result<Vec<Balance>, RouterError>;
+    ) -> Result<Balance, RouterError>;
 
+    /// Like `swap_exact_tokens_for_tokens`, unwrapping the output, the last token of the path must be wnative.
     #[ink(message)]
     fn swap_exact_tokens_for_native(
         &mut self,
         amount_in: Balance,
         amount_out_min: Balance,
         path: Vec<AccountId>,
+        fees: Vec<u32>,
         to: AccountId,
         deadline: u64,
-    ) -> Result<Vec<Balance>, RouterError>;
+    ) -> Result<Balance, RouterError>;
 
+    /// Like `swap_tokens_for_exact_tokens`, paid with the transferred value wrapped, `path[0]` must be wnative.
+    ///
+    /// The unused value is refunded to the caller.
     #[ink(message, payable)]
     fn swap_native_for_exact_tokens(
         &mut self,
         amount_out: Balance,
         path: Vec<AccountId>,
+        fees: Vec<u32>,
         to: AccountId,
         deadline: u64,
-    ) -> Result<Vec<Balance>, RouterError>;
-
-    #[ink(message)]
-    fn quote(
-        &self,
-        amount_a: Balance,
-        reserve_a: Balance,
-        reserve_b: Balance,
     ) -> Result<Balance, RouterError>;
-
-    #[ink(message)]
-    fn get_amount_out(
-        &self,
-        amount_in: Balance,
-        reserve_in: Balance,
-        reserve_out: Balance,
-    ) -> Result<Balance, RouterError>;
-
-    #[ink(message)]
-    fn get_amount_in(
-        &self,
-        amount_out: Balance,
-        reserve_in: Balance,
-        reserve_out: Balance,
-    ) -> Result<Balance, RouterError>;
-
-    #[ink(message)]
-    fn get_amounts_out(
-        &self,
-        amount_in: Balance,
-        path: Vec<AccountId>,
-    ) -> Result<Vec<Balance>, RouterError>;
-
-    #[ink(message)]
-    fn get_amounts_in(
-        &self,
-        amount_out: Balance,
-        path: Vec<AccountId>,
-    ) -> Result<Vec<Balance>, RouterError>;
 }
 
 #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
@@ -193,6 +131,7 @@ pub enum RouterError {
     DivByZero,
     TransferFailed,
     InvalidPath,
+    PoolCallFailed,
 }
 
 macro_rules! impl_froms {